@@ -22,69 +22,221 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 extern crate alloc;
-use alloc::vec::Vec;
-use bs58;
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    vec::Vec,
+};
 
 use concordium_cis2::*;
 use concordium_std::*;
 
-/// The baseurl for the token metadata, gets appended with the token ID as hex
-/// encoding before emitted in the TokenMetadata event.
-const TOKEN_METADATA_BASE_URL: &str = " https://web3id.backend.aesirx.io:8001/licenses/";
+/// Maximum length in bytes of the on-chain contract description.
+const MAX_DESCRIPTION_LENGTH: usize = 256;
 
-/// List of supported standards by this contract address.
+/// Maximum length in bytes of the contract logo URL.
+const MAX_LOGO_URL_LENGTH: usize = 256;
+
+/// Minimum time between queueing and executing a timelocked operation.
+const TIMELOCK_DELAY: Duration = Duration::from_days(2);
+
+/// Basis points making up the whole sale amount, the maximum royalty rate.
+const MAX_ROYALTY_BASIS_POINTS: u16 = 10_000;
+
+/// The version of the state layout of this module. Bump it with every change
+/// to the layout of `State`, and provide a migration as described on
+/// `execute_operation`.
+const STATE_VERSION: u16 = 1;
+
+/// The maximum number of events a single contract function call can log.
+const MAX_LOG_EVENTS: usize = 32;
+
+/// Bits of `State::paused_ops`, each pausing one kind of mutating entrypoint.
+/// Pauses `mint`, `mintNext`, `mintBatch`, `mintWithMetadata`, `mintFungible`
+/// and `distribute`.
+pub const PAUSE_MINT: u32 = 1 << 0;
+/// Pauses `transfer`.
+pub const PAUSE_TRANSFER: u32 = 1 << 1;
+/// Pauses `burn`.
+pub const PAUSE_BURN: u32 = 1 << 2;
+/// Pauses `updateOperator`.
+pub const PAUSE_UPDATE_OPERATOR: u32 = 1 << 3;
+/// Pauses `permit`, switching off sponsored transactions and CIS-3.
+pub const PAUSE_PERMIT: u32 = 1 << 4;
+
+/// The operations halted by the contract-wide pause switch.
+const CONTRACT_PAUSE_OPS: u32 = PAUSE_MINT | PAUSE_TRANSFER | PAUSE_BURN;
+
+/// The standard identifier for CIS-3, sponsored transactions through `permit`.
+const CIS3_STANDARD_IDENTIFIER: StandardIdentifier<'static> =
+    StandardIdentifier::new_unchecked("CIS-3");
+
+/// List of standards supported by this contract address regardless of which
+/// optional features are enabled.
 const SUPPORTS_STANDARDS: [StandardIdentifier<'static>; 2] =
     [CIS0_STANDARD_IDENTIFIER, CIS2_STANDARD_IDENTIFIER];
 
+/// Optional standards, each with the `PAUSE_*` bit of the entrypoint
+/// implementing it. They are supported only while that entrypoint is enabled.
+const OPTIONAL_STANDARDS: [(StandardIdentifier<'static>, u32); 1] =
+    [(CIS3_STANDARD_IDENTIFIER, PAUSE_PERMIT)];
+
+/// The entrypoints which can be invoked through `permit`.
+const PERMIT_ENTRYPOINTS: [&str; 2] = ["transfer", "updateOperator"];
+
+/// The confirmation `renounceOwnership` requires, against accidental calls.
+pub const RENOUNCE_CONFIRMATION: &str = "RENOUNCE";
+
+/// The owner after `renounceOwnership`: the address of a contract instance
+/// which can never exist, so no one can act as the owner.
+pub const RENOUNCED_OWNER: Address = Address::Contract(ContractAddress {
+    index:    u64::MAX,
+    subindex: u64::MAX,
+});
+
 // Types
 
 /// Contract token ID type.
 /// To save bytes we use a token ID type limited to a `u32`.
-type ContractTokenId = TokenIdU32;
+pub type ContractTokenId = TokenIdU32;
 
 /// Contract token amount.
-/// Since the tokens are non-fungible the total supply of any token will be at
-/// most 1 and it is fine to use a small type for representing token amounts.
-type ContractTokenAmount = TokenAmountU8;
+/// Most tokens are non-fungible with a total supply of at most 1, but fungible
+/// license pools (e.g. seats in a subscription) need larger amounts.
+pub type ContractTokenAmount = TokenAmountU64;
 
 // Web3Id, essentially a string
-type Web3Id = String;
+pub type Web3Id = String;
 
 #[derive(Debug, Serialize, Clone, SchemaType)]
 pub struct TokenMetadata {
     /// The URL following the specification RFC1738.
     #[concordium(size_length = 2)]
     pub url: String,
-    /// A optional hash of the content.
+    /// A optional SHA256 hash of the content.
+    pub hash: Option<[u8; 32]>,
+    /// An optional MIME type of the content, e.g. `application/json`.
+    pub content_type: Option<String>,
+    /// The time the license expires, if it expires at all.
+    pub expiry: Option<Timestamp>,
+    /// Additional URLs besides the primary `url`, e.g. of the legal document
+    /// or a thumbnail. Empty if the token has none.
     #[concordium(size_length = 2)]
-    pub hash: String,
+    pub additional_urls: Vec<(MetadataRole, String)>,
+    /// The block time the token was minted at.
+    pub issued_at: Timestamp,
+    /// The product tier of the license, e.g. to tell Basic, Pro and
+    /// Enterprise licenses apart. Zero for tokens minted without a tier.
+    pub tier: u8,
+}
+
+/// What an additional metadata URL of a token points to.
+#[derive(Debug, Serialize, SchemaType, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataRole {
+    /// The legal document of the license, e.g. a PDF.
+    Document,
+    /// An image to display for the license.
+    Thumbnail,
+    /// The terms of use of the license.
+    Terms,
 }
 
 /// The parameter for the contract function `mint` which mints a token to a given address
 #[derive(Serial, Deserial, SchemaType)]
-struct MintParams {
+pub struct MintParams {
     /// Owner of the newly minted token.
-    owner: AccountAddress,
+    pub owner: AccountAddress,
     /// Token
-    token: ContractTokenId,
+    pub token: ContractTokenId,
     /// Web3Id
-    web3id: Web3Id,
+    pub web3id: Web3Id,
+    /// Optional SHA256 hash pinning the content of the token metadata.
+    pub hash: Option<[u8; 32]>,
+    /// Optional MIME type of the token metadata content.
+    pub content_type: Option<String>,
+    /// Optional time the license expires.
+    pub expiry: Option<Timestamp>,
+    /// The product tier of the license.
+    pub tier: u8,
+}
+
+/// Why a token is burned, as reported in the `LicenseRevoked` event.
+#[derive(Debug, Serialize, SchemaType, Clone, Copy, PartialEq, Eq)]
+pub enum BurnReason {
+    /// The license has expired.
+    Expired,
+    /// The license was deliberately revoked.
+    Revoked,
+    /// The holder asked for the license to be burned.
+    OwnerRequested,
 }
 
 /// Parameter type for the burn function
 #[derive(Serial, Deserial, SchemaType)]
-struct BurnParams {
-    token_id: ContractTokenId,
-    owner: Address,
-    amount: ContractTokenAmount,
+pub struct BurnParams {
+    pub token_id: ContractTokenId,
+    pub owner: Address,
+    pub amount: ContractTokenAmount,
+    pub reason: BurnReason,
+}
+
+/// Short on-chain information describing the contract instance.
+/// Used both as the parameter of `setContractInfo` and the return value of
+/// `contractInfo`.
+#[derive(Debug, Serialize, SchemaType, Clone, Default, PartialEq, Eq)]
+pub struct ContractInfo {
+    /// A short description of the collection.
+    #[concordium(size_length = 2)]
+    pub description: String,
+    /// The URL of the collection logo.
+    #[concordium(size_length = 2)]
+    pub logo_url: String,
+}
+
+/// The parameter for the contract function `mintFungible` which mints an
+/// amount of a fungible license pool to a given address.
+#[derive(Serial, Deserial, SchemaType)]
+pub struct MintFungibleParams {
+    /// Owner of the newly minted amount.
+    pub owner: AccountAddress,
+    /// The fungible token.
+    pub token: ContractTokenId,
+    /// The amount to mint.
+    pub amount: ContractTokenAmount,
+}
+
+/// Roles granting access to privileged entrypoints.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq, Clone, Copy)]
+pub enum Role {
+    /// Can mint tokens.
+    Minter,
+    /// Can burn tokens of any holder.
+    Burner,
+    /// Can pause and unpause the contract, entrypoints and tokens.
+    Pauser,
+    /// Can grant and revoke roles.
+    Admin,
+}
+
+/// All roles, granted to the deployer at initialization.
+const ALL_ROLES: [Role; 4] = [Role::Minter, Role::Burner, Role::Pauser, Role::Admin];
+
+/// The parameter type for the contract functions `grantRole` and `revokeRole`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct RoleParams {
+    /// The address to grant the role to or revoke it from.
+    pub address: Address,
+    /// The role.
+    pub role:    Role,
 }
 
 /// The state for each address.
 #[derive(Serial, DeserialWithState, Deletable)]
 #[concordium(state_parameter = "S")]
 struct AddressState<S> {
-    /// The tokens owned by this address.
+    /// The non-fungible tokens owned by this address.
     owned_tokens: StateSet<ContractTokenId, S>,
+    /// The balances of the fungible tokens owned by this address.
+    balances: StateMap<ContractTokenId, ContractTokenAmount, S>,
     /// The address which are currently enabled as operators for this address.
     operators: StateSet<Address, S>,
 }
@@ -93,9 +245,38 @@ impl<S: HasStateApi> AddressState<S> {
     fn empty(state_builder: &mut StateBuilder<S>) -> Self {
         AddressState {
             owned_tokens: state_builder.new_set(),
+            balances: state_builder.new_map(),
             operators: state_builder.new_set(),
         }
     }
+
+    /// Check if the address holds any non-fungible token or a positive
+    /// amount of any fungible token.
+    fn holds_tokens(&self) -> bool {
+        // `StateSet::is_empty` only looks for an entry at the prefix of the set,
+        // which never exists, so look for a first element instead.
+        self.owned_tokens.iter().next().is_some()
+            || self.balances.iter().any(|(_, amount)| *amount > 0.into())
+    }
+
+    /// Collect the state of the address for the view functions.
+    /// The entries are sorted, so the output does not depend on the iteration
+    /// order of the state.
+    fn view(&self) -> ViewAddressState {
+        let mut owned_tokens: Vec<ContractTokenId> =
+            self.owned_tokens.iter().map(|x| *x).collect();
+        owned_tokens.sort_by_key(|token_id| token_id.0);
+        let mut balances: Vec<(ContractTokenId, ContractTokenAmount)> =
+            self.balances.iter().map(|(t, a)| (*t, *a)).collect();
+        balances.sort_by_key(|(token_id, _)| token_id.0);
+        let mut operators: Vec<Address> = self.operators.iter().map(|x| *x).collect();
+        operators.sort();
+        ViewAddressState {
+            owned_tokens,
+            balances,
+            operators,
+        }
+    }
 }
 
 /// The contract state.
@@ -108,6 +289,10 @@ struct State<S> {
     state: StateMap<Address, AddressState<S>, S>,
     /// All of the token IDs
     all_tokens: StateSet<ContractTokenId, S>,
+    /// The holder of each non-fungible token, the reverse of `owned_tokens`.
+    token_owners: StateMap<ContractTokenId, Address, S>,
+    /// The number of token IDs in `all_tokens`, kept to avoid iterating the set.
+    token_count: u64,
     /// Map with contract addresses providing implementations of additional
     /// standards.
     implementors: StateMap<StandardIdentifierOwned, Vec<ContractAddress>, S>,
@@ -117,24 +302,295 @@ struct State<S> {
     operators: StateSet<Address, S>,
     /// The owner of the contract
     owner: Address,
+    /// Description and logo of the contract, set by the owner.
+    contract_info: ContractInfo,
+    /// Hashes of queued timelocked operations with their earliest execution
+    /// time.
+    queued_operations: StateMap<HashSha2256, Timestamp, S>,
+    /// The total supply of each fungible token. Tokens without an entry are
+    /// non-fungible.
+    fungible_supply: StateMap<ContractTokenId, ContractTokenAmount, S>,
+    /// Contract-wide switch halting all minting, transfers and burns.
+    paused: bool,
+    /// Whether only accounts can hold tokens.
+    accounts_only: bool,
+    /// Whether mint recipients must already have an entry in `state`.
+    registered_recipients_only: bool,
+    /// The roles granted to each address.
+    roles: StateMap<Address, StateSet<Role, S>, S>,
+    /// Bitset of paused entrypoints, see the `PAUSE_*` constants.
+    paused_ops: u32,
+    /// Tokens which can currently neither be transferred nor burned.
+    paused_tokens: StateSet<ContractTokenId, S>,
+    /// The time each non-fungible token was minted.
+    minted_at: StateMap<ContractTokenId, Timestamp, S>,
+    /// How long after minting a token can only be transferred by the owner
+    /// of the contract or an `Admin`.
+    lockup: Duration,
+    /// The base URL for the token metadata, gets appended with the token
+    /// number before emitted in the TokenMetadata event.
+    metadata_base_url: String,
+    /// The web3id each license was minted for.
+    web3ids: StateMap<ContractTokenId, Web3Id, S>,
+    /// The next nonce expected in a message signed by each account.
+    nonces: StateMap<AccountAddress, u64, S>,
+    /// The receiver and rate in basis points of royalties on resales.
+    default_royalty: (Address, u16),
+    /// Royalty receivers and rates overriding the default for single tokens.
+    token_royalties: StateMap<ContractTokenId, (Address, u16), S>,
+    /// The inclusive range of token IDs each minter is restricted to.
+    /// Minters without a range can mint any token ID.
+    reserved_ranges: StateMap<Address, (u32, u32), S>,
+    /// The token ID `mintNext` tries next. It is part of the state root, so
+    /// it is kept across upgrades as long as the state layout is migrated.
+    next_id: u32,
+    /// Whether metadata URLs use the token ID as is instead of swapping its
+    /// byte order, see `build_token_metadata_url`.
+    raw_token_id_urls: bool,
+    /// Burned tokens which were revoked, until their token ID is minted again.
+    revoked: StateSet<ContractTokenId, S>,
+    /// Addresses which can neither send nor receive tokens.
+    frozen: StateSet<Address, S>,
+    /// The version of the state layout, see `STATE_VERSION`.
+    state_version: u16,
+    /// Single-use approvals to burn a token, by holder and token. Cleared
+    /// when the token is burned or leaves the holder.
+    burn_approvals: StateMap<(Address, ContractTokenId), Address, S>,
+    /// The operators which may transfer a single token on behalf of its owner,
+    /// by owner and token, in addition to the operators for all tokens.
+    /// Cleared when the token is burned or leaves the owner.
+    scoped_operators: StateMap<(Address, ContractTokenId), StateSet<Address, S>, S>,
+    /// Whether `onReceivingCIS2` accepts tokens of other contracts in escrow.
+    escrow_enabled: bool,
+    /// The tokens of other contracts held in escrow by this contract, by
+    /// token contract and token, with the address which sent them. The token
+    /// IDs are kept in their raw form, since other contracts need not use
+    /// `ContractTokenId`.
+    escrowed: StateMap<(ContractAddress, TokenIdVec), Address, S>,
+    /// The number of entries in `state`, so `viewPaginated` does not have to
+    /// count them.
+    address_count: u32,
+    /// An address tokens cannot be transferred to, since transferring to it
+    /// would fake a burn without a `Burn` event.
+    blackhole: Option<Address>,
+    /// The URL of the collection-level metadata, e.g. its name, description
+    /// and image. The URL is empty if it has not been set.
+    collection_metadata: MetadataUrl,
+    /// The number of licenses ever minted to each account.
+    mint_counts: StateMap<AccountAddress, u32, S>,
+    /// The maximum number of licenses which can ever be minted to a single
+    /// account, if limited.
+    per_account_mint_cap: Option<u32>,
+    /// The number of distinct tokens each address holds: its owned
+    /// non-fungible tokens and the fungible tokens it has a balance of.
+    owned_counts: StateMap<Address, u32, S>,
+    /// The existing tokens of each tier, so `tokensByTier` only iterates the
+    /// tokens of the queried tier.
+    tier_tokens: StateMap<u8, StateSet<ContractTokenId, S>, S>,
 }
 
 /// The parameter type for the contract function `setImplementors`.
 /// Takes a standard identifier and list of contract addresses providing
 /// implementations of this standard.
 #[derive(Debug, Serialize, SchemaType)]
-struct SetImplementorsParams {
+pub struct SetImplementorsParams {
     /// The identifier for the standard.
-    id: StandardIdentifierOwned,
+    pub id: StandardIdentifierOwned,
     /// The addresses of the implementors of the standard.
-    implementors: Vec<ContractAddress>,
+    pub implementors: Vec<ContractAddress>,
+}
+
+/// The parameter type for the contract function `transferOwnership`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct TransferOwnershipParams {
+    /// The address which becomes the new owner of the contract.
+    pub new_owner: Address,
+    /// Deliberately allow a contract as the new owner. A contract which
+    /// cannot call the owner-only entrypoints locks them forever.
+    pub allow_contract_owner: bool,
+}
+
+impl TransferOwnershipParams {
+    /// Construct the parameter from a Base58 encoded account address, e.g. as
+    /// copied from a wallet.
+    pub fn from_account_str(new_owner: &str) -> Result<Self, CustomContractError> {
+        // Decode the new owner address from Base58Check with version byte 1.
+        let new_owner_bytes = bs58::decode(new_owner)
+            .with_check(Some(1))
+            .into_vec()
+            .map_err(|_| CustomContractError::ParseParams)?;
+
+        // Ensure the byte array following the version byte is exactly 32 bytes
+        let new_owner = AccountAddress(
+            new_owner_bytes[1..].try_into().map_err(|_| CustomContractError::ParseParams)?,
+        );
+
+        Ok(TransferOwnershipParams {
+            new_owner: Address::Account(new_owner),
+            allow_contract_owner: false,
+        })
+    }
+}
+
+/// An event logged when the ownership of the contract is transferred.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct OwnershipTransferredEvent {
+    /// The owner before the transfer.
+    pub previous_owner: Address,
+    /// The owner after the transfer.
+    pub new_owner: Address,
+}
+
+/// An event logged when the contract is upgraded to a new module.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct UpgradedEvent {
+    /// The module the contract was upgraded to.
+    pub module: ModuleReference,
+}
+
+/// The kind of action executed on behalf of a signer by a sponsor.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq, Clone, Copy)]
+pub enum SponsoredAction {
+    /// A `transfer` was executed.
+    Transfer,
+    /// An `updateOperator` was executed.
+    UpdateOperator,
+}
+
+/// An event logged by signature-verified entrypoints, so indexers can
+/// correlate a sponsored transaction with the nonce it consumed.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct SponsoredExecutedEvent {
+    /// The account which signed the message.
+    pub signer: AccountAddress,
+    /// The nonce consumed by the message.
+    pub nonce:  u64,
+    /// The action executed on behalf of the signer.
+    pub action: SponsoredAction,
+}
+
+/// An event logged when the expiry of a license is extended with `renew`.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct LicenseRenewedEvent {
+    /// The renewed license.
+    pub token_id:   ContractTokenId,
+    /// The new expiry of the license.
+    pub new_expiry: Timestamp,
+}
+
+/// An event logged when a role is granted to or revoked from an address.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct RoleEvent {
+    /// The address the role was granted to or revoked from.
+    pub address: Address,
+    /// The role.
+    pub role:    Role,
+}
+
+/// An event logged when the contract is paused or unpaused.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct PauseEvent {
+    /// The `Pauser` which paused or unpaused the contract.
+    pub pauser:     Address,
+    /// The block time the contract was paused or unpaused at.
+    pub block_time: Timestamp,
+}
+
+/// An event logged alongside the CIS2 `Burn` event, stating why the token
+/// was burned.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct LicenseRevokedEvent {
+    /// The burned token.
+    pub token_id: ContractTokenId,
+    /// The holder of the burned token.
+    pub owner:    Address,
+    /// Why the token was burned.
+    pub reason:   BurnReason,
+}
+
+/// An event logged when a global minting operator is added or removed.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct GlobalOperatorUpdatedEvent {
+    /// The operator added or removed.
+    pub operator: Address,
+    /// Whether the operator was added or removed.
+    pub update:   OperatorUpdate,
+}
+
+/// An event logged when an operator of an owner for a single token is added
+/// or removed.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct ScopedOperatorUpdatedEvent {
+    /// The owner of the token.
+    pub owner:    Address,
+    /// The operator added or removed.
+    pub operator: Address,
+    /// The token the operator may transfer.
+    pub token_id: ContractTokenId,
+    /// Whether the operator was added or removed.
+    pub update:   OperatorUpdate,
+}
+
+/// Custom events logged by this contract in addition to the CIS2 events.
+/// The tags count down from 250 so they never collide with the tags used by
+/// `Cis2Event`.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+#[concordium(repr(u8))]
+pub enum CustomEvent {
+    /// The ownership of the contract was transferred.
+    #[concordium(tag = 250)]
+    OwnershipTransferred(OwnershipTransferredEvent),
+    /// The contract was upgraded.
+    #[concordium(tag = 249)]
+    Upgraded(UpgradedEvent),
+    /// A signed message was executed on behalf of its signer.
+    #[concordium(tag = 248)]
+    SponsoredExecuted(SponsoredExecutedEvent),
+    /// The expiry of a license was extended.
+    #[concordium(tag = 247)]
+    LicenseRenewed(LicenseRenewedEvent),
+    /// A role was granted to an address.
+    #[concordium(tag = 246)]
+    RoleGranted(RoleEvent),
+    /// A role was revoked from an address.
+    #[concordium(tag = 245)]
+    RoleRevoked(RoleEvent),
+    /// The contract was paused.
+    #[concordium(tag = 244)]
+    Paused(PauseEvent),
+    /// The contract was unpaused.
+    #[concordium(tag = 243)]
+    Unpaused(PauseEvent),
+    /// A global minting operator was added or removed.
+    #[concordium(tag = 242)]
+    GlobalOperatorUpdated(GlobalOperatorUpdatedEvent),
+    /// A token was burned, for the given reason.
+    #[concordium(tag = 241)]
+    LicenseRevoked(LicenseRevokedEvent),
+    /// An operator of an owner for a single token was added or removed.
+    #[concordium(tag = 240)]
+    ScopedOperatorUpdated(ScopedOperatorUpdatedEvent),
+}
+
+/// All events logged by this contract. Only used to describe the events in the
+/// embedded schema, the events themselves are logged as either `Cis2Event` or
+/// `CustomEvent`.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+#[concordium(repr(u8))]
+pub enum ContractEvent {
+    /// A CIS2 event.
+    #[concordium(forward = cis2_events)]
+    Cis2(Cis2Event<ContractTokenId, ContractTokenAmount>),
+    /// A custom event, the tags must list every tag of `CustomEvent`.
+    #[concordium(forward = [250, 249, 248, 247, 246, 245, 244, 243, 242, 241, 240])]
+    Custom(CustomEvent),
 }
 
-/// The custom errors the contract can produce.
-#[derive(Serialize, Debug, PartialEq, Eq, Reject, SchemaType)]
-enum CustomContractError {
+/// The custom errors the contract can produce. Their reject codes are fixed,
+/// see `CustomContractError::reject_code`.
+#[derive(Serialize, Debug, PartialEq, Eq, SchemaType)]
+pub enum CustomContractError {
     /// Failed parsing the parameter.
-    #[from(ParseError)]
     ParseParams,
     /// Failed logging: Log is full.
     LogFull,
@@ -150,13 +606,164 @@ enum CustomContractError {
     /// License not found
     LicenseNotFound,
     Unauthorized,
+    /// The contract description or logo URL exceeds its maximum length.
+    ContractInfoTooLong,
+    /// The total supply of a fungible token would overflow.
+    SupplyOverflow,
+    /// The entrypoint is currently paused.
+    OperationPaused,
+    /// The contract is paused.
+    ContractPaused,
+    /// Transfers and burns of the token are paused.
+    TokenPaused,
+    /// Only accounts are allowed to hold tokens.
+    ContractHoldersForbidden,
+    /// The mint recipient has not been registered.
+    RecipientNotRegistered,
+    /// The timelocked operation is already queued.
+    OperationAlreadyQueued,
+    /// The timelocked operation has not been queued.
+    OperationNotQueued,
+    /// The delay of the timelocked operation has not passed yet.
+    TimelockNotElapsed,
+    /// The token is still in its lockup period after minting.
+    LockupActive,
+    /// The nonce of the signed message is not the current nonce of the signer.
+    NonceMismatch,
+    /// The signed message is intended for another contract.
+    WrongContract,
+    /// The signed message has expired.
+    Expired,
+    /// The signature does not match the signer and message.
+    WrongSignature,
+    /// The signed message targets an entrypoint which cannot be sponsored.
+    WrongEntryPoint,
+    /// The signer account does not exist.
+    MissingAccount,
+    /// The signature data is malformed.
+    MalformedData,
+    /// The royalty rate exceeds 10000 basis points.
+    InvalidRoyalty,
+    /// The token ID is outside the range reserved for the minter.
+    TokenIdOutOfRange,
+    /// The token range is empty, i.e. its start is after its end.
+    InvalidTokenRange,
+    /// The token range overlaps the range reserved for another minter.
+    TokenRangeOverlap,
+    /// The receiving contract does not implement the CIS2 receive hook or
+    /// rejected the tokens.
+    ReceiveHookFailed,
+    /// The sender or recipient of the tokens is frozen.
+    AddressFrozen,
+    /// The new module uses another state version, but no migration is given.
+    MigrationRequired,
+    /// The state version does not match the state version of the module.
+    StateVersionMismatch,
+    /// An address cannot be added as its own operator.
+    SelfOperator,
+    /// The metadata URL is malformed.
+    InvalidMetadataUrl,
+    /// The new owner is a contract, which was not explicitly allowed.
+    InvalidOwner,
+    /// Escrow is disabled, or the received tokens are not a single NFT.
+    EscrowRejected,
+    /// The token is not held in escrow by this contract.
+    NotEscrowed,
+    /// Tokens cannot be transferred to the blackhole address, they must be
+    /// burned with `burn` instead.
+    UseBurnInstead,
+    /// The token exists, but the owner does not hold it.
+    NotTokenOwner,
+    /// The account already reached the maximum number of licenses minted to
+    /// it.
+    MintQuotaExceeded,
+    /// The confirmation to renounce the ownership is missing or wrong.
+    RenounceNotConfirmed,
+    /// The counter of `mintNext` cannot advance past `u32::MAX`.
+    TokenIdSpaceExhausted,
+    /// The contract instance holds less CCD than requested.
+    InsufficientBalance,
+    /// The callback of `transferAndCall` rejected.
+    CallbackFailed,
 }
 
 /// Wrapping the custom errors in a type with CIS2 errors.
-type ContractError = Cis2Error<CustomContractError>;
+pub type ContractError = Cis2Error<CustomContractError>;
 
 type ContractResult<A> = Result<A, ContractError>;
 
+impl CustomContractError {
+    /// The reject code of the error, which off-chain tooling can rely on
+    /// across upgrades. Codes count down from `-1` in the order the variants
+    /// were introduced. New variants take the next free code and the code of
+    /// a removed variant is never reused, so reordering the enum does not
+    /// shift any code.
+    pub fn reject_code(&self) -> i32 {
+        match self {
+            Self::ParseParams => -1,
+            Self::LogFull => -2,
+            Self::LogMalformed => -3,
+            Self::TokenIdAlreadyExists => -4,
+            Self::InvokeContractError => -5,
+            Self::InvalidWeb3Id => -6,
+            Self::LicenseNotFound => -7,
+            Self::Unauthorized => -8,
+            Self::ContractInfoTooLong => -9,
+            Self::SupplyOverflow => -10,
+            Self::OperationPaused => -11,
+            Self::ContractPaused => -12,
+            Self::TokenPaused => -13,
+            Self::ContractHoldersForbidden => -14,
+            Self::RecipientNotRegistered => -15,
+            Self::OperationAlreadyQueued => -16,
+            Self::OperationNotQueued => -17,
+            Self::TimelockNotElapsed => -18,
+            Self::LockupActive => -19,
+            Self::NonceMismatch => -20,
+            Self::WrongContract => -21,
+            Self::Expired => -22,
+            Self::WrongSignature => -23,
+            Self::WrongEntryPoint => -24,
+            Self::MissingAccount => -25,
+            Self::MalformedData => -26,
+            Self::InvalidRoyalty => -27,
+            Self::TokenIdOutOfRange => -28,
+            Self::InvalidTokenRange => -29,
+            Self::TokenRangeOverlap => -30,
+            Self::ReceiveHookFailed => -31,
+            Self::AddressFrozen => -32,
+            Self::MigrationRequired => -33,
+            Self::StateVersionMismatch => -34,
+            Self::SelfOperator => -35,
+            Self::InvalidMetadataUrl => -36,
+            Self::InvalidOwner => -37,
+            Self::EscrowRejected => -38,
+            Self::NotEscrowed => -39,
+            Self::UseBurnInstead => -40,
+            Self::NotTokenOwner => -41,
+            Self::MintQuotaExceeded => -42,
+            Self::RenounceNotConfirmed => -43,
+            Self::TokenIdSpaceExhausted => -44,
+            Self::InsufficientBalance => -45,
+            Self::CallbackFailed => -46,
+        }
+    }
+}
+
+/// Mapping CustomContractError to a reject with its fixed reject code.
+impl From<CustomContractError> for Reject {
+    fn from(error: CustomContractError) -> Self {
+        Reject::new(error.reject_code()).unwrap_or_default()
+    }
+}
+
+/// Mapping the parameter parsing errors to CustomContractError.
+impl From<ParseError> for CustomContractError {
+    fn from(_: ParseError) -> Self {
+        Self::ParseParams
+    }
+}
+
 /// Mapping the logging errors to CustomContractError.
 impl From<LogError> for CustomContractError {
     fn from(le: LogError) -> Self {
@@ -174,6 +781,26 @@ impl<T> From<CallContractError<T>> for CustomContractError {
     }
 }
 
+/// Mapping errors of CCD transfers to CustomContractError.
+impl From<TransferError> for CustomContractError {
+    fn from(e: TransferError) -> Self {
+        match e {
+            TransferError::AmountTooLarge => Self::InsufficientBalance,
+            TransferError::MissingAccount => Self::MissingAccount,
+        }
+    }
+}
+
+/// Mapping account signature checking errors to CustomContractError.
+impl From<CheckAccountSignatureError> for CustomContractError {
+    fn from(e: CheckAccountSignatureError) -> Self {
+        match e {
+            CheckAccountSignatureError::MissingAccount => Self::MissingAccount,
+            CheckAccountSignatureError::MalformedData => Self::MalformedData,
+        }
+    }
+}
+
 /// Mapping CustomContractError to ContractError
 impl From<CustomContractError> for ContractError {
     fn from(c: CustomContractError) -> Self {
@@ -181,11 +808,25 @@ impl From<CustomContractError> for ContractError {
     }
 }
 
-fn build_token_metadata_url(token_id: &ContractTokenId) -> String {
-    // Swap the byte order of the token id to get the natural incremental number.
-    let token_value = token_id.0.swap_bytes();
-    // Format the number as an 8-digit decimal string with leading zeros.
-    format!("{}{:08}", TOKEN_METADATA_BASE_URL, token_value)
+/// Build the metadata URL of a token by appending the token number to the
+/// configured base URL.
+///
+/// By default the byte order of the token ID is swapped first, since token IDs
+/// used to be created from the big-endian encoding of an incremental number;
+/// token `1` then becomes `16777216`. With `raw` the `u32` value of the token
+/// ID is used as is.
+fn build_token_metadata_url(base_url: &str, token_id: &ContractTokenId, raw: bool) -> String {
+    // Unless raw, swap the byte order of the token id to get the natural
+    // incremental number.
+    let token_value = if raw {
+        token_id.0
+    } else {
+        token_id.0.swap_bytes()
+    };
+    // Format the number as an 8-digit decimal string with leading zeros. The
+    // base URL is trimmed, since URLs with surrounding whitespace are not
+    // valid RFC1738 URLs.
+    format!("{}{:08}", base_url.trim(), token_value)
 }
 
 // Functions for creating, updating and querying the contract state.
@@ -195,91 +836,381 @@ impl<S: HasStateApi> State<S> {
         State {
             state: state_builder.new_map(),
             all_tokens: state_builder.new_set(),
+            token_owners: state_builder.new_map(),
+            token_count: 0,
             implementors: state_builder.new_map(),
             metadata: state_builder.new_map(),
             operators: state_builder.new_set(),
             owner,
+            contract_info: ContractInfo::default(),
+            queued_operations: state_builder.new_map(),
+            fungible_supply: state_builder.new_map(),
+            paused: false,
+            accounts_only: false,
+            registered_recipients_only: false,
+            roles: state_builder.new_map(),
+            paused_ops: 0,
+            paused_tokens: state_builder.new_set(),
+            minted_at: state_builder.new_map(),
+            lockup: Duration::from_millis(0),
+            metadata_base_url: String::new(),
+            web3ids: state_builder.new_map(),
+            nonces: state_builder.new_map(),
+            default_royalty: (owner, 0),
+            token_royalties: state_builder.new_map(),
+            reserved_ranges: state_builder.new_map(),
+            next_id: 1,
+            raw_token_id_urls: false,
+            revoked: state_builder.new_set(),
+            frozen: state_builder.new_set(),
+            state_version: STATE_VERSION,
+            burn_approvals: state_builder.new_map(),
+            scoped_operators: state_builder.new_map(),
+            escrow_enabled: false,
+            escrowed: state_builder.new_map(),
+            blackhole: None,
+            collection_metadata: MetadataUrl {
+                url:  String::new(),
+                hash: None,
+            },
+            mint_counts: state_builder.new_map(),
+            per_account_mint_cap: None,
+            owned_counts: state_builder.new_map(),
+            tier_tokens: state_builder.new_map(),
+            address_count: 0,
         }
     }
 
-    /// Internal burn helper function. Invokes the burn functionality of the state.
-/// Logs a Burn event. The function assumes that the burn is authorized.
+    /// Burn a token held by `owner`. The function assumes that the burn is
+    /// authorized and leaves logging the `Burn` event to the caller.
+    /// Rejects with `InvalidTokenId` if the token does not exist and with
+    /// `NotTokenOwner` if it exists but the owner does not hold it.
     fn burn(
         &mut self,
         token: &ContractTokenId,
+        amount: ContractTokenAmount,
         owner: &Address,
     ) -> ContractResult<()> {
         ensure!(self.contains_token(token), ContractError::InvalidTokenId);
 
+        if self.is_fungible(token) {
+            return self.burn_fungible(token, amount, owner);
+        }
+        // An NFT can only be burned as a whole.
+        ensure_eq!(amount, 1.into(), ContractError::InsufficientFunds);
+
         if let Some(mut address_state) = self.state.get_mut(owner) {
             ensure!(
                 address_state.owned_tokens.remove(token),
-                ContractError::InsufficientFunds
+                CustomContractError::NotTokenOwner.into()
             );
         } else {
-            bail!(ContractError::InsufficientFunds)
+            bail!(CustomContractError::NotTokenOwner.into())
         }
+        self.decrement_owned_count(owner);
+
+        self.token_owners.remove(token);
+        self.burn_approvals.remove(&(*owner, *token));
+        self.scoped_operators.remove(&(*owner, *token));
+        self.minted_at.remove(token);
+        self.web3ids.remove(token);
+        self.token_royalties.remove(token);
 
         // Remove token from all tokens
-        self.all_tokens.remove(token);
-        
-        // Remove token metadata
+        if self.all_tokens.remove(token) {
+            self.token_count -= 1;
+        }
+
+        // Remove the token from its tier and its metadata.
+        if let Some(metadata) = self.metadata.get(token) {
+            if let Some(mut tokens) = self.tier_tokens.get_mut(&metadata.tier) {
+                tokens.remove(token);
+            }
+        }
         self.metadata.remove(token);
 
         Ok(())
     }
 
+    /// Burn an amount of a fungible token. The token itself keeps existing,
+    /// even if its supply drops to zero.
+    fn burn_fungible(
+        &mut self,
+        token: &ContractTokenId,
+        amount: ContractTokenAmount,
+        owner: &Address,
+    ) -> ContractResult<()> {
+        let emptied = {
+            let mut address_state =
+                self.state.get_mut(owner).ok_or(ContractError::InsufficientFunds)?;
+            let mut balance = address_state
+                .balances
+                .get_mut(token)
+                .ok_or(ContractError::InsufficientFunds)?;
+            ensure!(*balance >= amount, ContractError::InsufficientFunds);
+            *balance -= amount;
+            amount != 0.into() && *balance == 0.into()
+        };
+        if emptied {
+            self.decrement_owned_count(owner);
+        }
+
+        let mut supply = self.fungible_supply.get_mut(token).ok_or(ContractError::InvalidTokenId)?;
+        *supply -= amount;
+        Ok(())
+    }
+
+    /// Mint an amount of a fungible token with a given address as the owner.
+    /// The first mint of a token ID creates the fungible pool, later mints
+    /// increase its supply. Returns whether the pool was created.
+    fn mint_fungible(
+        &mut self,
+        token: ContractTokenId,
+        amount: ContractTokenAmount,
+        owner: &Address,
+        now: Timestamp,
+        state_builder: &mut StateBuilder<S>,
+    ) -> ContractResult<bool> {
+        self.ensure_valid_mint_recipient(owner)?;
+        if let Address::Account(account) = owner {
+            self.count_mint(account)?;
+        }
+        let created = self.all_tokens.insert(token);
+        if created {
+            self.token_count += 1;
+            let _ = self.metadata.insert(token, TokenMetadata {
+                url: self.token_metadata_url(&token),
+                hash: None,
+                content_type: None,
+                expiry: None,
+                additional_urls: Vec::new(),
+                issued_at: now,
+                tier: 0,
+            });
+            self.index_tier(0, token, state_builder);
+            let _ = self.fungible_supply.insert(token, 0.into());
+        }
+
+        {
+            // An existing token without a supply entry is non-fungible.
+            let mut supply = self
+                .fungible_supply
+                .get_mut(&token)
+                .ok_or(CustomContractError::TokenIdAlreadyExists)?;
+            let new_supply =
+                supply.0.checked_add(amount.0).ok_or(CustomContractError::SupplyOverflow)?;
+            *supply = new_supply.into();
+        }
+
+        let filled = {
+            let mut owner_state = self.address_state_mut(owner, state_builder);
+            let mut balance = owner_state.balances.entry(token).or_insert(0.into());
+            let filled = amount != 0.into() && *balance == 0.into();
+            *balance += amount;
+            filled
+        };
+        if filled {
+            self.increment_owned_count(owner);
+        }
+        Ok(created)
+    }
+
+    /// Get the total supply of a token across all holders.
+    /// Results in an error if the token ID does not exist in the state.
+    fn total_supply(&self, token_id: &ContractTokenId) -> ContractResult<ContractTokenAmount> {
+        ensure!(self.contains_token(token_id), ContractError::InvalidTokenId);
+        // Every existing NFT has a supply of exactly 1.
+        let supply = self
+            .fungible_supply
+            .get(token_id)
+            .map(|supply| *supply)
+            .unwrap_or_else(|| 1.into());
+        Ok(supply)
+    }
+
+    /// Get the holder of a non-fungible token.
+    /// Returns `None` for fungible tokens and tokens which do not exist.
+    fn owner_of(&self, token_id: &ContractTokenId) -> Option<Address> {
+        self.token_owners.get(token_id).map(|owner| *owner)
+    }
+
+    /// Check if the token is a fungible token.
+    #[inline(always)]
+    fn is_fungible(&self, token_id: &ContractTokenId) -> bool {
+        self.fungible_supply.get(token_id).is_some()
+    }
 
     /// Mint a new token with a given address as the owner
     fn mint(
         &mut self,
         token: ContractTokenId,
-        metadata_url: &String,
+        metadata: TokenMetadata,
+        owner: &Address,
+        now: Timestamp,
+        state_builder: &mut StateBuilder<S>,
+    ) -> ContractResult<()> {
+        self.ensure_valid_mint_recipient(owner)?;
+        if let Address::Account(account) = owner {
+            self.count_mint(account)?;
+        }
+        self.insert_token(token, metadata, owner, now, state_builder)
+    }
+
+    /// Replace a license with a new token held by the same owner, keeping the
+    /// terms of the old license: its content type, expiry, tier and
+    /// additional URLs. Unlike `mint`, the replacement neither counts towards
+    /// the mint cap of the owner nor checks the owner as a recipient, since
+    /// the owner already held the license. Returns the owner and the metadata
+    /// of the new token.
+    fn reissue(
+        &mut self,
+        old_token_id: &ContractTokenId,
+        new_token_id: ContractTokenId,
+        url: String,
+        hash: [u8; 32],
+        now: Timestamp,
+        state_builder: &mut StateBuilder<S>,
+    ) -> ContractResult<(Address, TokenMetadata)> {
+        let owner = self.owner_of(old_token_id).ok_or(CustomContractError::LicenseNotFound)?;
+        let old_metadata = self
+            .metadata
+            .get(old_token_id)
+            .map(|metadata| metadata.clone())
+            .ok_or(CustomContractError::LicenseNotFound)?;
+
+        self.burn(old_token_id, 1.into(), &owner)?;
+        self.revoked.insert(*old_token_id);
+
+        let metadata = TokenMetadata {
+            url,
+            hash: Some(hash),
+            issued_at: now,
+            ..old_metadata
+        };
+        self.insert_token(new_token_id, metadata.clone(), &owner, now, state_builder)?;
+        Ok((owner, metadata))
+    }
+
+    /// Store a new non-fungible token held by the owner, shared by `mint` and
+    /// `reissue`.
+    fn insert_token(
+        &mut self,
+        token: ContractTokenId,
+        metadata: TokenMetadata,
         owner: &Address,
+        now: Timestamp,
         state_builder: &mut StateBuilder<S>,
     ) -> ContractResult<()> {
         ensure!(
             self.all_tokens.insert(token),
             CustomContractError::TokenIdAlreadyExists.into()
         );
+        self.token_count += 1;
+        self.index_tier(metadata.tier, token, state_builder);
+        let _ = self.metadata.insert(token, metadata);
 
-        let metadata_url = build_token_metadata_url(&token);
-        let metadata = TokenMetadata {
-            url: metadata_url,
-            hash: String::from(""),
-        };
+        let mut owner_state = self.address_state_mut(owner, state_builder);
+        owner_state.owned_tokens.insert(token);
+        drop(owner_state);
+        self.increment_owned_count(owner);
+        let _ = self.token_owners.insert(token, *owner);
+        let _ = self.minted_at.insert(token, now);
+        self.revoked.remove(&token);
+        Ok(())
+    }
 
-        self.metadata.insert(token, metadata.clone());
+    /// Add a new token to the index of its tier.
+    fn index_tier(
+        &mut self,
+        tier: u8,
+        token: ContractTokenId,
+        state_builder: &mut StateBuilder<S>,
+    ) {
+        self.tier_tokens.entry(tier).or_insert_with(|| state_builder.new_set()).insert(token);
+    }
 
-        let mut owner_state = self
-            .state
-            .entry(*owner)
-            .or_insert_with(|| AddressState::empty(state_builder));
-        owner_state.owned_tokens.insert(token);
+    /// Count a license minted to an account, rejecting if the account already
+    /// reached the mint cap.
+    fn count_mint(&mut self, account: &AccountAddress) -> ContractResult<()> {
+        let mut count = self.mint_counts.entry(*account).or_insert(0);
+        if let Some(cap) = self.per_account_mint_cap {
+            ensure!(*count < cap, CustomContractError::MintQuotaExceeded.into());
+        }
+        *count += 1;
         Ok(())
     }
 
+    /// Get the metadata URL and checksum of a token as returned by
+    /// `tokenMetadata`, falling back to the URL built from the base URL if no
+    /// metadata is stored. Tokens usually have stored metadata, so the
+    /// existence of the token is only checked when the lookup fails.
+    fn metadata_url_of(&self, token_id: &ContractTokenId) -> ContractResult<MetadataUrl> {
+        match self.metadata.get(token_id) {
+            Some(metadata) => Ok(MetadataUrl {
+                hash: metadata.hash,
+                url: metadata.url.clone(),
+            }),
+            None => {
+                ensure!(self.contains_token(token_id), ContractError::InvalidTokenId);
+                Ok(MetadataUrl {
+                    hash: None,
+                    url: self.token_metadata_url(token_id),
+                })
+            }
+        }
+    }
+
+    /// Build the metadata URL of a token minted without web3id.
+    fn token_metadata_url(&self, token_id: &ContractTokenId) -> String {
+        build_token_metadata_url(&self.metadata_base_url, token_id, self.raw_token_id_urls)
+    }
+
+    /// Take the next token ID from the counter, skipping IDs which were
+    /// minted explicitly. The counter never wraps: once it cannot advance
+    /// past `u32::MAX` the token IDs are exhausted.
+    fn next_token_id(&mut self) -> ContractResult<ContractTokenId> {
+        loop {
+            let token_id = TokenIdU32(self.next_id);
+            self.next_id =
+                self.next_id.checked_add(1).ok_or(CustomContractError::TokenIdSpaceExhausted)?;
+            if !self.contains_token(&token_id) {
+                return Ok(token_id);
+            }
+        }
+    }
+
+    /// Drop the stored metadata of an existing token, as left behind by a
+    /// partial migration.
+    #[cfg(test)]
+    fn remove_metadata(&mut self, token_id: &ContractTokenId) {
+        self.metadata.remove(token_id);
+    }
+
     /// Check that the token ID currently exists in this contract.
     #[inline(always)]
     fn contains_token(&self, token_id: &ContractTokenId) -> bool {
         self.all_tokens.contains(token_id)
     }
 
-    /// Get the current balance of a given token ID for a given address.
+    /// Get the current balance of a given token ID for an address, given its
+    /// already fetched state, `None` if the address has no state.
     /// Results in an error if the token ID does not exist in the state.
-    /// Since this contract only contains NFTs, the balance will always be
-    /// either 1 or 0.
-    fn balance(
+    /// For NFTs, the balance will always be either 1 or 0 and is looked up in
+    /// the `token_owners` index instead of the state of the address.
+    fn balance_in(
         &self,
         token_id: &ContractTokenId,
         address: &Address,
+        address_state: Option<&AddressState<S>>,
     ) -> ContractResult<ContractTokenAmount> {
         ensure!(self.contains_token(token_id), ContractError::InvalidTokenId);
-        let balance = self
-            .state
-            .get(address)
-            .map(|address_state| u8::from(address_state.owned_tokens.contains(token_id)))
-            .unwrap_or(0);
+        if self.is_fungible(token_id) {
+            let balance = address_state
+                .and_then(|address_state| address_state.balances.get(token_id).map(|b| *b))
+                .unwrap_or_else(|| 0.into());
+            return Ok(balance);
+        }
+        let balance = u64::from(self.owner_of(token_id) == Some(*address));
         Ok(balance.into())
     }
 
@@ -291,6 +1222,28 @@ impl<S: HasStateApi> State<S> {
             .unwrap_or(false)
     }
 
+    /// Consume the current nonce of a signer, returning it.
+    fn consume_nonce(&mut self, signer: AccountAddress) -> u64 {
+        let mut entry = self.nonces.entry(signer).or_insert(0);
+        let nonce = *entry;
+        *entry += 1;
+        nonce
+    }
+
+    /// Check if a given address is an operator of a given owner address for
+    /// a single token.
+    fn is_scoped_operator(
+        &self,
+        address: &Address,
+        owner: &Address,
+        token_id: &ContractTokenId,
+    ) -> bool {
+        self.scoped_operators
+            .get(&(*owner, *token_id))
+            .map(|operators| operators.contains(address))
+            .unwrap_or(false)
+    }
+
     /// Update the state with a transfer of some token.
     /// Results in an error if the token ID does not exist in the state or if
     /// the from address have insufficient tokens to do the transfer.
@@ -303,13 +1256,17 @@ impl<S: HasStateApi> State<S> {
         state_builder: &mut StateBuilder<S>,
     ) -> ContractResult<()> {
         ensure!(self.contains_token(token_id), ContractError::InvalidTokenId);
+        self.ensure_valid_holder(to)?;
         // A zero transfer does not modify the state.
         if amount == 0.into() {
             return Ok(());
         }
-        // Since this contract only contains NFTs, no one will have an amount greater
-        // than 1. And since the amount cannot be the zero at this point, the
-        // address must have insufficient funds for any amount other than 1.
+        if self.is_fungible(token_id) {
+            return self.transfer_fungible(token_id, amount, from, to, state_builder);
+        }
+        // Since no one holds an amount greater than 1 of an NFT, and since the
+        // amount cannot be the zero at this point, the address must have
+        // insufficient funds for any amount other than 1.
         ensure_eq!(amount, 1.into(), ContractError::InsufficientFunds);
 
         {
@@ -322,122 +1279,473 @@ impl<S: HasStateApi> State<S> {
             let from_had_the_token = from_address_state.owned_tokens.remove(token_id);
             ensure!(from_had_the_token, ContractError::InsufficientFunds);
         }
+        self.burn_approvals.remove(&(*from, *token_id));
+        self.scoped_operators.remove(&(*from, *token_id));
+        self.decrement_owned_count(from);
 
         // Add the token to the new owner.
-        let mut to_address_state = self
-            .state
-            .entry(*to)
-            .or_insert_with(|| AddressState::empty(state_builder));
+        let mut to_address_state = self.address_state_mut(to, state_builder);
         to_address_state.owned_tokens.insert(*token_id);
+        drop(to_address_state);
+        self.increment_owned_count(to);
+        let _ = self.token_owners.insert(*token_id, *to);
         Ok(())
     }
 
-    /// Update the state adding a new operator for minting tokens
-    /// Succeeds even if the `operator` is already an operator for the
-    /// `address`.
-    fn add_global_operator(&mut self, operator: &Address) {
-        self.operators.insert(*operator);
+    /// Count a token newly held by an address.
+    fn increment_owned_count(&mut self, owner: &Address) {
+        let mut count = self.owned_counts.entry(*owner).or_insert(0);
+        *count += 1;
     }
 
-    /// Update the state removing an operator for minting tokens
-    /// Succeeds even if the `operator` is _not_ an operator for the
-    /// `address`.
-    fn remove_global_operator(&mut self, operator: &Address) {
-        self.operators.remove(operator);
+    /// Count a token no longer held by an address.
+    fn decrement_owned_count(&mut self, owner: &Address) {
+        self.owned_counts.entry(*owner).and_modify(|count| *count = count.saturating_sub(1));
     }
-    /// Update the state adding a new operator for a given address.
-    /// Succeeds even if the `operator` is already an operator for the
-    /// `address`.
-    fn add_operator(
-        &mut self,
-        owner: &Address,
-        operator: &Address,
-        state_builder: &mut StateBuilder<S>,
-    ) {
-        let mut owner_state = self
-            .state
-            .entry(*owner)
-            .or_insert_with(|| AddressState::empty(state_builder));
-        owner_state.operators.insert(*operator);
+
+    /// Reject if the operation identified by the `PAUSE_*` bit is paused,
+    /// either by the contract-wide switch or selectively.
+    fn ensure_not_paused(&self, operation: u32) -> ContractResult<()> {
+        ensure!(
+            !self.paused || operation & CONTRACT_PAUSE_OPS == 0,
+            CustomContractError::ContractPaused.into()
+        );
+        ensure!(
+            self.paused_ops & operation == 0,
+            CustomContractError::OperationPaused.into()
+        );
+        Ok(())
     }
 
-    /// Update the state removing an operator for a given address.
-    /// Succeeds even if the `operator` is _not_ an operator for the `address`.
-    fn remove_operator(&mut self, owner: &Address, operator: &Address) {
-        self.state.entry(*owner).and_modify(|address_state| {
-            address_state.operators.remove(operator);
-        });
+    /// Reject if transfers and burns of the token are paused.
+    fn ensure_token_not_paused(&self, token_id: &ContractTokenId) -> ContractResult<()> {
+        ensure!(
+            !self.paused_tokens.contains(token_id),
+            CustomContractError::TokenPaused.into()
+        );
+        Ok(())
     }
 
-    /// Check if state contains any implementors for a given standard.
-    fn have_implementors(&self, std_id: &StandardIdentifierOwned) -> SupportResult {
-        if let Some(addresses) = self.implementors.get(std_id) {
-            SupportResult::SupportBy(addresses.to_vec())
-        } else {
-            SupportResult::NoSupport
+    /// Reject if the token was minted less than `lockup` before `now`.
+    fn ensure_lockup_elapsed(
+        &self,
+        token_id: &ContractTokenId,
+        now: Timestamp,
+    ) -> ContractResult<()> {
+        if let Some(minted_at) = self.minted_at.get(token_id) {
+            ensure!(
+                minted_at.checked_add(self.lockup).is_some_and(|end| now >= end),
+                CustomContractError::LockupActive.into()
+            );
         }
+        Ok(())
     }
 
-    /// Set implementors for a given standard.
-    fn set_implementors(
-        &mut self,
-        std_id: StandardIdentifierOwned,
-        implementors: Vec<ContractAddress>,
-    ) {
-        self.implementors.insert(std_id, implementors);
+    /// Reject token IDs outside the range reserved for the minter, if any.
+    fn ensure_in_reserved_range(
+        &self,
+        minter: &Address,
+        token_id: &ContractTokenId,
+    ) -> ContractResult<()> {
+        if let Some(range) = self.reserved_ranges.get(minter) {
+            let (start, end) = *range;
+            ensure!(
+                start <= token_id.0 && token_id.0 <= end,
+                CustomContractError::TokenIdOutOfRange.into()
+            );
+        }
+        Ok(())
     }
-}
 
-/// Build a string from TOKEN_METADATA_BASE_URL appended with the web3id
-/// encoded as hex.
-// fn build_token_metadata_url(web3id: &Web3Id) -> String {
-//     let mut token_metadata_url = String::from(TOKEN_METADATA_BASE_URL);
-//     token_metadata_url.push_str(&web3id.to_string());
-//     token_metadata_url
-// }
+    /// Reserve an inclusive range of token IDs for a minter, replacing its
+    /// previous range. Results in an error if the range is empty or overlaps
+    /// the range of another minter.
+    fn reserve_range(&mut self, minter: &Address, start: u32, end: u32) -> ContractResult<()> {
+        ensure!(start <= end, CustomContractError::InvalidTokenRange.into());
+        let overlaps = self.reserved_ranges.iter().any(|(other, range)| {
+            let (other_start, other_end) = *range;
+            *other != *minter && start <= other_end && other_start <= end
+        });
+        ensure!(!overlaps, CustomContractError::TokenRangeOverlap.into());
+        let _ = self.reserved_ranges.insert(*minter, (start, end));
+        Ok(())
+    }
 
-/// Function to evaluate a web3 id format
-// fn check_web3id(s: &str) -> bool {
-//     if s.starts_with('@') && s.len() >= 4 && s.len() <= 21 {
-//         let username = &s[1..];
-//         if username.chars().all(|c| c.is_alphanumeric() || c == '_') {
-//             return true;
-//         }
-//     }
-//     false
-// }
+    /// Reject frozen addresses as senders or recipients of tokens.
+    fn ensure_not_frozen(&self, address: &Address) -> ContractResult<()> {
+        ensure!(!self.frozen.contains(address), CustomContractError::AddressFrozen.into());
+        Ok(())
+    }
 
-// Contract functions
+    /// Reject recipients which are not allowed to hold tokens.
+    fn ensure_valid_holder(&self, address: &Address) -> ContractResult<()> {
+        ensure!(
+            !self.accounts_only || matches!(address, Address::Account(_)),
+            CustomContractError::ContractHoldersForbidden.into()
+        );
+        Ok(())
+    }
 
-/// Initialize contract instance with no token types initially.
-#[init(
-    contract = "LicenseContract",
-    event = "Cis2Event<ContractTokenId, ContractTokenAmount>"
-)]
-fn contract_init<S: HasStateApi>(
+    /// Reject transfers to the blackhole address, which would fake a burn.
+    fn ensure_not_blackhole(&self, address: &Address) -> ContractResult<()> {
+        ensure!(
+            self.blackhole.as_ref() != Some(address),
+            CustomContractError::UseBurnInstead.into()
+        );
+        Ok(())
+    }
+
+    /// Reject mint recipients without an entry in the state, if registration
+    /// is required.
+    fn ensure_registered_recipient(&self, address: &Address) -> ContractResult<()> {
+        ensure!(
+            !self.registered_recipients_only || self.state.get(address).is_some(),
+            CustomContractError::RecipientNotRegistered.into()
+        );
+        Ok(())
+    }
+
+    /// Reject recipients tokens cannot be minted to. Every mint path runs
+    /// these checks, so further recipient checks belong here.
+    fn ensure_valid_mint_recipient(&self, address: &Address) -> ContractResult<()> {
+        self.ensure_valid_holder(address)?;
+        self.ensure_registered_recipient(address)?;
+        self.ensure_not_frozen(address)
+    }
+
+    /// Create an empty entry for the address, if it has none yet.
+    fn register_address(&mut self, address: &Address, state_builder: &mut StateBuilder<S>) {
+        self.address_state_mut(address, state_builder);
+    }
+
+    /// Get the state of an address, creating an empty entry if it has none
+    /// yet. Every entry is created here, so `address_count` stays in step
+    /// with the number of entries.
+    fn address_state_mut(
+        &mut self,
+        address: &Address,
+        state_builder: &mut StateBuilder<S>,
+    ) -> OccupiedEntry<'_, Address, AddressState<S>, S> {
+        if self.state.get(address).is_none() {
+            self.address_count += 1;
+        }
+        self.state.entry(*address).or_insert_with(|| AddressState::empty(state_builder))
+    }
+
+    /// Check if the address is allowed to mint tokens, i.e. it has the
+    /// `Minter` role or is a global operator.
+    fn can_mint(&self, address: &Address) -> bool {
+        self.has_role(address, Role::Minter) || self.operators.contains(address)
+    }
+
+    /// Check if the address can change the expiry of licenses, i.e. it is the
+    /// owner or has the `Admin` or `Minter` role.
+    fn can_set_expiry(&self, address: &Address) -> bool {
+        *address == self.owner
+            || self.has_role(address, Role::Admin)
+            || self.has_role(address, Role::Minter)
+    }
+
+    /// Check if the address has been granted the role.
+    fn has_role(&self, address: &Address, role: Role) -> bool {
+        self.roles
+            .get(address)
+            .map(|roles| roles.contains(&role))
+            .unwrap_or(false)
+    }
+
+    /// Grant a role to an address.
+    /// Succeeds even if the address already has the role.
+    fn grant_role(
+        &mut self,
+        address: &Address,
+        role: Role,
+        state_builder: &mut StateBuilder<S>,
+    ) {
+        self.roles
+            .entry(*address)
+            .or_insert_with(|| state_builder.new_set())
+            .modify(|roles| roles.insert(role));
+    }
+
+    /// Revoke a role from an address.
+    /// Succeeds even if the address does not have the role.
+    fn revoke_role(&mut self, address: &Address, role: Role) {
+        self.roles.entry(*address).and_modify(|roles| {
+            roles.remove(&role);
+        });
+    }
+
+    /// Move an amount of a fungible token between two addresses.
+    fn transfer_fungible(
+        &mut self,
+        token_id: &ContractTokenId,
+        amount: ContractTokenAmount,
+        from: &Address,
+        to: &Address,
+        state_builder: &mut StateBuilder<S>,
+    ) -> ContractResult<()> {
+        let emptied = {
+            let mut from_address_state =
+                self.state.get_mut(from).ok_or(ContractError::InsufficientFunds)?;
+            let mut from_balance = from_address_state
+                .balances
+                .get_mut(token_id)
+                .ok_or(ContractError::InsufficientFunds)?;
+            ensure!(*from_balance >= amount, ContractError::InsufficientFunds);
+            *from_balance -= amount;
+            *from_balance == 0.into()
+        };
+        if emptied {
+            self.decrement_owned_count(from);
+        }
+
+        let filled = {
+            let mut to_address_state = self.address_state_mut(to, state_builder);
+            let mut to_balance = to_address_state.balances.entry(*token_id).or_insert(0.into());
+            let filled = *to_balance == 0.into();
+            *to_balance += amount;
+            filled
+        };
+        if filled {
+            self.increment_owned_count(to);
+        }
+        Ok(())
+    }
+
+    /// Update the state adding a new operator for minting tokens
+    /// Succeeds even if the `operator` is already an operator for the
+    /// `address`.
+    fn add_global_operator(&mut self, operator: &Address) {
+        self.operators.insert(*operator);
+    }
+
+    /// Update the state removing an operator for minting tokens
+    /// Succeeds even if the `operator` is _not_ an operator for the
+    /// `address`.
+    fn remove_global_operator(&mut self, operator: &Address) {
+        self.operators.remove(operator);
+    }
+
+    /// Update the state adding a new operator for a given address.
+    /// Succeeds even if the `operator` is already an operator for the
+    /// `address`.
+    fn add_operator(
+        &mut self,
+        owner: &Address,
+        operator: &Address,
+        state_builder: &mut StateBuilder<S>,
+    ) {
+        let mut owner_state = self.address_state_mut(owner, state_builder);
+        owner_state.operators.insert(*operator);
+    }
+
+    /// Update the state removing an operator for a given address.
+    /// Succeeds even if the `operator` is _not_ an operator for the `address`.
+    fn remove_operator(&mut self, owner: &Address, operator: &Address) {
+        self.state.entry(*owner).and_modify(|address_state| {
+            address_state.operators.remove(operator);
+        });
+    }
+
+    /// Check if the contract itself implements a standard. Optional standards
+    /// are only reported while the entrypoint implementing them is not paused.
+    fn supports_natively(&self, std_id: &StandardIdentifier) -> bool {
+        SUPPORTS_STANDARDS.contains(std_id)
+            || OPTIONAL_STANDARDS.iter().any(|(optional, operation)| {
+                optional == std_id && self.paused_ops & operation == 0
+            })
+    }
+
+    /// Get the implementors stored for a given standard, if any.
+    fn implementors_of(&self, std_id: &StandardIdentifierOwned) -> Option<Vec<ContractAddress>> {
+        self.implementors.get(std_id).map(|addresses| addresses.clone())
+    }
+
+    /// Count the addresses currently holding at least one token. Addresses
+    /// whose state only carries operators or zero balances are not counted.
+    fn holder_count(&self) -> u64 {
+        self.state
+            .iter()
+            .filter(|(_, address_state)| address_state.holds_tokens())
+            .count() as u64
+    }
+
+    /// Queue an operation hash to become executable at `earliest_execution`.
+    fn queue_operation(
+        &mut self,
+        operation_hash: HashSha2256,
+        earliest_execution: Timestamp,
+    ) -> ContractResult<()> {
+        ensure!(
+            self.queued_operations.insert(operation_hash, earliest_execution).is_none(),
+            CustomContractError::OperationAlreadyQueued.into()
+        );
+        Ok(())
+    }
+
+    /// Remove a queued operation, provided its timelock has elapsed at `now`.
+    fn take_matured_operation(
+        &mut self,
+        operation_hash: &HashSha2256,
+        now: Timestamp,
+    ) -> ContractResult<()> {
+        let earliest_execution = self
+            .queued_operations
+            .get(operation_hash)
+            .map(|timestamp| *timestamp)
+            .ok_or(CustomContractError::OperationNotQueued)?;
+        ensure!(
+            now >= earliest_execution,
+            CustomContractError::TimelockNotElapsed.into()
+        );
+        self.queued_operations.remove(operation_hash);
+        Ok(())
+    }
+
+    /// Set implementors for a given standard.
+    fn set_implementors(
+        &mut self,
+        std_id: StandardIdentifierOwned,
+        implementors: Vec<ContractAddress>,
+    ) {
+        let _ = self.implementors.insert(std_id, implementors);
+    }
+}
+
+/// Build the metadata URL of a license by appending the web3id to the
+/// configured base URL. The web3id must have been validated with
+/// `check_web3id`.
+fn build_web3id_metadata_url(base_url: &str, web3id: &Web3Id) -> String {
+    let mut token_metadata_url = String::from(base_url.trim());
+    token_metadata_url.push_str(web3id);
+    token_metadata_url
+}
+
+/// Function to evaluate a web3 id format: an `@` followed by ASCII letters,
+/// digits or underscores, 4 to 21 characters in total.
+pub fn check_web3id(s: &str) -> bool {
+    if s.starts_with('@') && s.len() >= 4 && s.len() <= 21 {
+        let username = &s[1..];
+        // Only ASCII, since the web3id becomes part of the metadata URL.
+        if username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return true;
+        }
+    }
+    false
+}
+
+/// Sanity check a metadata URL following RFC1738: a scheme of ASCII letters,
+/// digits, `+`, `-` or `.` starting with a letter, a `:` and a non-empty rest
+/// of printable ASCII characters without spaces.
+pub fn check_metadata_url(url: &str) -> bool {
+    let (scheme, rest) = match url.split_once(':') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid_scheme && !rest.is_empty() && rest.chars().all(|c| c.is_ascii_graphic())
+}
+
+/// Compute the checksum of a license: the SHA256 hash of the serialized token
+/// ID followed by the UTF-8 bytes of the web3id.
+fn compute_checksum(
+    token_id: &ContractTokenId,
+    web3id: &str,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> [u8; 32] {
+    let mut bytes = to_bytes(token_id);
+    bytes.extend_from_slice(web3id.as_bytes());
+    crypto_primitives.hash_sha2_256(&bytes).0
+}
+
+// Contract functions
+
+/// The parameter type for the contract initialization.
+#[derive(Debug, Serialize, SchemaType, Default)]
+pub struct InitParams {
+    /// Only accounts can hold tokens, contracts are rejected as recipients.
+    pub accounts_only: bool,
+    /// Tokens can only be minted to addresses registered with
+    /// `registerAddress` or already present in the state.
+    pub registered_recipients_only: bool,
+    /// The base URL of the token metadata. Surrounding whitespace is trimmed.
+    pub metadata_base_url: String,
+    /// Format token IDs in metadata URLs as is, instead of swapping their
+    /// byte order first.
+    pub raw_token_id_urls: bool,
+    /// Accept NFTs of other CIS-2 contracts in escrow, see `onReceivingCIS2`.
+    pub escrow_enabled: bool,
+    /// An address used as a burn sentinel, which tokens cannot be transferred
+    /// to. Tokens must be burned with `burn` instead.
+    pub blackhole: Option<Address>,
+    /// The URL of the collection-level metadata, see `contractMetadata`.
+    pub collection_metadata_url: String,
+    /// The maximum number of licenses which can ever be minted to a single
+    /// account, e.g. for trial licenses. Unlimited if `None`.
+    pub per_account_mint_cap: Option<u32>,
+    /// The owner of the contract instance, holding every role. Defaults to the
+    /// account initializing the instance.
+    pub owner: Option<AccountAddress>,
+    /// The addresses to add as global operators, which can mint right away.
+    pub initial_operators: Vec<Address>,
+}
+
+/// Initialize contract instance with no token types initially.
+/// CCD sent along are kept by the instance and can be withdrawn by the owner
+/// with `withdraw`.
+#[init(
+    contract = "LicenseContract",
+    parameter = "InitParams",
+    event = "ContractEvent",
+    payable
+)]
+fn contract_init<S: HasStateApi>(
     ctx: &impl HasInitContext,
     state_builder: &mut StateBuilder<S>,
+    _amount: Amount,
 ) -> InitResult<State<S>> {
+    // Parse the parameter.
+    let params: InitParams = ctx.parameter_cursor().get()?;
+
     // Use the init_origin as the default owner
-    let default_owner = ctx.init_origin();
+    let owner = Address::Account(params.owner.unwrap_or_else(|| ctx.init_origin()));
+
+    // Create the initial state with the given owner or the deployer as the owner
+    let mut state = State::empty(state_builder, owner);
+    state.accounts_only = params.accounts_only;
+    state.registered_recipients_only = params.registered_recipients_only;
+    state.metadata_base_url = params.metadata_base_url.trim().to_string();
+    state.raw_token_id_urls = params.raw_token_id_urls;
+    state.escrow_enabled = params.escrow_enabled;
+    state.blackhole = params.blackhole;
+    state.collection_metadata.url = params.collection_metadata_url;
+    state.per_account_mint_cap = params.per_account_mint_cap;
 
-    // Create the initial state with the deployer as the owner
-    let state = State::empty(state_builder, Address::Account(default_owner));
+    // The owner keeps all privileges by holding every role.
+    for role in ALL_ROLES {
+        state.grant_role(&owner, role, state_builder);
+    }
+    for operator in params.initial_operators.iter() {
+        state.add_global_operator(operator);
+    }
 
     Ok(state)
 }
 
-#[derive(Serialize, SchemaType)]
-struct ViewAddressState {
-    owned_tokens: Vec<ContractTokenId>,
-    operators: Vec<Address>,
+#[derive(Debug, Serialize, SchemaType)]
+pub struct ViewAddressState {
+    pub owned_tokens: Vec<ContractTokenId>,
+    pub balances: Vec<(ContractTokenId, ContractTokenAmount)>,
+    pub operators: Vec<Address>,
 }
 
-#[derive(Serialize, SchemaType)]
-struct ViewState {
-    state: Vec<(Address, ViewAddressState)>,
-    all_tokens: Vec<ContractTokenId>,
-    operators: Vec<Address>,
+#[derive(Debug, Serialize, SchemaType)]
+pub struct ViewState {
+    pub state: Vec<(Address, ViewAddressState)>,
+    pub all_tokens: Vec<ContractTokenId>,
+    pub operators: Vec<Address>,
 }
 
 #[receive(
@@ -454,18 +1762,44 @@ fn contract_burn<S: HasStateApi>(
     logger: &mut impl HasLogger,
 ) -> ContractResult<()> {
     // Parse the parameter.
-    let BurnParams { token_id, owner, amount } = ctx.parameter_cursor().get()?;
-    
-    let state = host.state();
+    let params: BurnParams = ctx.parameter_cursor().get()?;
+    host.state().ensure_not_paused(PAUSE_BURN)?;
 
     // Get the sender who invoked this contract function.
     let sender = ctx.sender();
+    burn_token(host.state_mut(), logger, sender, params)
+}
+
+/// Burn a token on behalf of `sender` and log a `Burn` and a `LicenseRevoked`
+/// event, shared by `burn` and `burnBatch`.
+fn burn_token<S: HasStateApi>(
+    state: &mut State<S>,
+    logger: &mut impl HasLogger,
+    sender: Address,
+    params: BurnParams,
+) -> ContractResult<()> {
+    let BurnParams {
+        token_id,
+        owner,
+        amount,
+        reason,
+    } = params;
+    state.ensure_token_not_paused(&token_id)?;
 
-    // Authenticate the sender for the token burns.
-    ensure!(owner == sender, ContractError::Unauthorized);
+    // Authenticate the sender for the token burns, either the owner, an
+    // operator of the owner or a `Burner`.
+    ensure!(
+        owner == sender
+            || state.is_operator(&sender, &owner)
+            || state.has_role(&sender, Role::Burner),
+        ContractError::Unauthorized
+    );
 
     // Burn the token
-    host.state_mut().burn(&token_id, &owner)?;
+    state.burn(&token_id, amount, &owner)?;
+    if reason == BurnReason::Revoked && !state.is_fungible(&token_id) {
+        state.revoked.insert(token_id);
+    }
 
     // Log the burn event with proper event emission
     logger.log(&Cis2Event::Burn(BurnEvent {
@@ -473,506 +1807,4076 @@ fn contract_burn<S: HasStateApi>(
         amount,
         owner,
     }))?;
+    logger.log(&CustomEvent::LicenseRevoked(LicenseRevokedEvent {
+        token_id,
+        owner,
+        reason,
+    }))?;
 
     Ok(())
 }
 
-/// View function that returns the entire contents of the state. Meant for
-/// testing.
+/// The parameter type for the contract function `approveBurn`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct ApproveBurnParams {
+    /// The token of the sender to approve the burn of.
+    pub token_id: ContractTokenId,
+    /// The address allowed to burn the token once.
+    pub spender:  Address,
+}
+
+/// Allow an address to burn one token of the sender once with `burnFrom`,
+/// e.g. an escrow. Replaces an earlier approval for the token.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - The sender does not hold the token.
 #[receive(
     contract = "LicenseContract",
-    name = "view",
-    return_value = "ViewState"
+    name = "approveBurn",
+    parameter = "ApproveBurnParams",
+    error = "ContractError",
+    mutable
 )]
-fn contract_view<S: HasStateApi>(
-    _ctx: &impl HasReceiveContext,
-    host: &impl HasHost<State<S>, StateApiType = S>,
-) -> ReceiveResult<ViewState> {
-    let state = host.state();
+fn contract_approve_burn<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let ApproveBurnParams { token_id, spender } = ctx.parameter_cursor().get()?;
 
-    let mut inner_state = Vec::new();
-    for (k, a_state) in state.state.iter() {
-        let owned_tokens = a_state.owned_tokens.iter().map(|x| *x).collect();
-        let operators = a_state.operators.iter().map(|x| *x).collect();
-        inner_state.push((
-            *k,
-            ViewAddressState {
-                owned_tokens,
-                operators,
-            },
-        ));
-    }
-    let all_tokens = state.all_tokens.iter().map(|x| *x).collect();
-    let operators = state.operators.iter().map(|x| *x).collect();
+    let sender = ctx.sender();
+    let state = host.state_mut();
+    ensure!(state.owner_of(&token_id) == Some(sender), ContractError::Unauthorized);
+    let _ = state.burn_approvals.insert((sender, token_id), spender);
+    Ok(())
+}
 
-    Ok(ViewState {
-        state: inner_state,
-        all_tokens,
-        operators,
-    })
+/// The parameter type for the contract function `burnFrom`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct BurnFromParams {
+    /// The token to burn.
+    pub token_id: ContractTokenId,
+    /// The holder of the token, who approved the burn.
+    pub owner:    Address,
+    /// Why the token is burned.
+    pub reason:   BurnReason,
 }
 
-/// Mint new tokens with a given address as the owner of these tokens.
-/// Can only be called by the contract owner.
-/// Logs a `Mint` and a `TokenMetadata` event for each token.
-/// The url for the token metadata is the token ID encoded in hex, appended on
-/// the `TOKEN_METADATA_BASE_URL`.
+/// Burn a token using a burn approval of its holder, consuming the approval.
+/// Logs a `Burn` and a `LicenseRevoked` event.
 ///
 /// It rejects if:
-/// - The sender is not the contract instance owner.
-/// - Fails to parse parameter.
-/// - Any of the tokens fails to be minted, which could be if:
-///     - The minted token ID already exists.
-///     - Fails to log Mint event
-///     - Fails to log TokenMetadata event
-///
-/// Note: Can at most mint 32 token types in one call due to the limit on the
-/// number of logs a smart contract can produce on each function call.
+/// - It fails to parse the parameter.
+/// - Burning or the token is paused.
+/// - The sender is not approved to burn the token of the owner.
+/// - Fails to log events.
 #[receive(
     contract = "LicenseContract",
-    name = "mint",
-    parameter = "MintParams",
+    name = "burnFrom",
+    parameter = "BurnFromParams",
     error = "ContractError",
     enable_logger,
     mutable
 )]
-fn contract_mint<S: HasStateApi>(
+fn contract_burn_from<S: HasStateApi>(
     ctx: &impl HasReceiveContext,
     host: &mut impl HasHost<State<S>, StateApiType = S>,
     logger: &mut impl HasLogger,
 ) -> ContractResult<()> {
-    // Get the contract owner
-    let owner = ctx.owner();
-    // Get the sender of the transaction
-    let sender = ctx.sender();
-
-    let (state, builder) = host.state_and_builder();
-
-    if sender != state.owner && !state.operators.contains(&sender) {
-        return Err(ContractError::Unauthorized); // Use the stored owner and operators for authorization
-    }
-
-    // Only the owner account and global operators can mint
-    // ensure!(
-    //     sender.matches_account(&owner) || state.operators.contains(&sender),
-    //     ContractError::Unauthorized
-    // );
-
     // Parse the parameter.
-    let params: MintParams = ctx.parameter_cursor().get()?;
-
-    let token_id = params.token;
-    let web3id = params.web3id;
-    // let token_be = u32::from_be_bytes(token_id.to_le_bytes());
-
-    // ensure!(
-    //     // check_web3id(&web3id),
-    //     CustomContractError::InvalidWeb3Id.into()
-    // );
+    let BurnFromParams { token_id, owner, reason } = ctx.parameter_cursor().get()?;
 
-    // let metadata_url = build_token_metadata_url(&web3id);
-    let metadata_url = build_token_metadata_url(&token_id);
-
-    let token_owner: Address = Address::Account(params.owner);
+    let sender = ctx.sender();
+    let state = host.state_mut();
+    state.ensure_not_paused(PAUSE_BURN)?;
+    state.ensure_token_not_paused(&token_id)?;
+    let approved = state.burn_approvals.remove_and_get(&(owner, token_id));
+    ensure!(approved == Some(sender), ContractError::Unauthorized);
 
-    // Mint the token in the state.
-    state.mint(token_id, &metadata_url, &token_owner, builder)?;
+    let amount = ContractTokenAmount::from(1);
+    state.burn(&token_id, amount, &owner)?;
+    if reason == BurnReason::Revoked {
+        state.revoked.insert(token_id);
+    }
 
-    // Event for minted NFT.
-    logger.log(&Cis2Event::Mint(MintEvent {
+    logger.log(&Cis2Event::Burn(BurnEvent {
         token_id,
-        amount: ContractTokenAmount::from(1),
-        owner: token_owner,
+        amount,
+        owner,
+    }))?;
+    logger.log(&CustomEvent::LicenseRevoked(LicenseRevokedEvent {
+        token_id,
+        owner,
+        reason,
     }))?;
-
-    // Metadata URL for the NFT.
-    logger.log(&Cis2Event::TokenMetadata::<_, ContractTokenAmount>(
-        TokenMetadataEvent {
-            token_id,
-            metadata_url: MetadataUrl {
-                url: metadata_url,
-                hash: None,
-            },
-        },
-    ))?;
     Ok(())
 }
 
-type TransferParameter = TransferParams<ContractTokenId, ContractTokenAmount>;
-
-/// Execute a list of token transfers, in the order of the list.
-///
-/// Logs a `Transfer` event and invokes a receive hook function for every
-/// transfer in the list.
+/// Burn several tokens in one call, authorizing each burn individually.
+/// Logs a `Burn` and a `LicenseRevoked` event for each token. If any burn fails, none of the tokens
+/// are burned.
 ///
 /// It rejects if:
 /// - It fails to parse the parameter.
-/// - Any of the transfers fail to be executed, which could be if:
-///     - The `token_id` does not exist.
-///     - The sender is not the owner of the token, or an operator for this
-///       specific `token_id` and `from` address.
-///     - The token is not owned by the `from`.
-/// - Fails to log event.
-/// - Any of the receive hook function calls rejects.
+/// - The events of all the burns do not fit in the log limit.
+/// - Any of the burns fails, which could be if:
+///     - The sender is neither the owner, an operator of the owner nor a
+///       `Burner`.
+///     - The token does not exist or is not owned by the owner.
 #[receive(
     contract = "LicenseContract",
-    name = "transfer",
-    parameter = "TransferParameter",
+    name = "burnBatch",
+    parameter = "Vec<BurnParams>",
     error = "ContractError",
     enable_logger,
     mutable
 )]
-fn contract_transfer<S: HasStateApi>(
+fn contract_burn_batch<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let burns: Vec<BurnParams> = ctx.parameter_cursor().get()?;
+    host.state().ensure_not_paused(PAUSE_BURN)?;
+    ensure!(burns.len() * 2 <= MAX_LOG_EVENTS, CustomContractError::LogFull.into());
+
+    let sender = ctx.sender();
+    let state = host.state_mut();
+    for params in burns {
+        burn_token(state, logger, sender, params)?;
+    }
+    Ok(())
+}
+
+/// View function that returns the entire contents of the state, with tokens
+/// and operators sorted. Meant for testing only: the return value grows with
+/// the state and eventually exceeds the energy and size limits, use
+/// `viewPaginated` instead.
+#[receive(
+    contract = "LicenseContract",
+    name = "view",
+    return_value = "ViewState"
+)]
+fn contract_view<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<ViewState> {
+    let state = host.state();
+
+    let mut inner_state = Vec::new();
+    for (k, a_state) in state.state.iter() {
+        inner_state.push((*k, a_state.view()));
+    }
+    let mut all_tokens: Vec<ContractTokenId> = state.all_tokens.iter().map(|x| *x).collect();
+    all_tokens.sort_by_key(|token_id| token_id.0);
+    let mut operators: Vec<Address> = state.operators.iter().map(|x| *x).collect();
+    operators.sort();
+
+    Ok(ViewState {
+        state: inner_state,
+        all_tokens,
+        operators,
+    })
+}
+
+/// The parameter type for the contract function `viewPaginated`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct ViewPaginatedParams {
+    /// The number of addresses to skip.
+    pub skip: u32,
+    /// The maximum number of addresses to return.
+    pub take: u32,
+}
+
+/// The return type of the contract function `viewPaginated`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct ViewPage {
+    /// The state of the addresses in the page.
+    pub state: Vec<(Address, ViewAddressState)>,
+    /// The total number of addresses in the state.
+    pub total: u32,
+}
+
+/// Get the state of a page of addresses. The addresses are always iterated in
+/// the same order, so consecutive pages are consistent as long as no address
+/// is added in between.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "viewPaginated",
+    parameter = "ViewPaginatedParams",
+    return_value = "ViewPage",
+    error = "ContractError"
+)]
+fn contract_view_paginated<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<ViewPage> {
+    // Parse the parameter.
+    let params: ViewPaginatedParams = ctx.parameter_cursor().get()?;
+    let state = host.state();
+
+    let page = state
+        .state
+        .iter()
+        .skip(params.skip as usize)
+        .take(params.take as usize)
+        .map(|(address, address_state)| (*address, address_state.view()))
+        .collect();
+    Ok(ViewPage {
+        state: page,
+        total: state.address_count,
+    })
+}
+
+/// Get the time each queried token was minted at, or `None` for tokens which
+/// do not exist. Unlike `expiry`, the issuance time never changes.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "issuanceInfo",
+    parameter = "Vec<ContractTokenId>",
+    return_value = "Vec<Option<Timestamp>>",
+    error = "ContractError"
+)]
+fn contract_issuance_info<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<Vec<Option<Timestamp>>> {
+    // Parse the parameter.
+    let token_ids: Vec<ContractTokenId> = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    let issued = token_ids
+        .iter()
+        .map(|token_id| state.metadata.get(token_id).map(|metadata| metadata.issued_at))
+        .collect();
+    Ok(issued)
+}
+
+/// Get the tier of each queried token, or `None` for tokens which do not
+/// exist or whose metadata was reset.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "tierOf",
+    parameter = "Vec<ContractTokenId>",
+    return_value = "Vec<Option<u8>>",
+    error = "ContractError"
+)]
+fn contract_tier_of<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<Vec<Option<u8>>> {
+    // Parse the parameter.
+    let token_ids: Vec<ContractTokenId> = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    let tiers = token_ids
+        .iter()
+        .map(|token_id| state.metadata.get(token_id).map(|metadata| metadata.tier))
+        .collect();
+    Ok(tiers)
+}
+
+/// The parameter type for the contract function `tokensByTier`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct TokensByTierParams {
+    /// The tier to list the tokens of.
+    pub tier: u8,
+    /// The number of matching tokens to skip.
+    pub skip: u32,
+    /// The maximum number of matching tokens to return.
+    pub take: u32,
+}
+
+/// Get a page of the tokens of a tier. Only the tokens of the tier are
+/// iterated, in the order of the tier index. The order is stable across calls,
+/// but follows the serialized token IDs rather than their numeric order.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "tokensByTier",
+    parameter = "TokensByTierParams",
+    return_value = "Vec<ContractTokenId>",
+    error = "ContractError"
+)]
+fn contract_tokens_by_tier<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<Vec<ContractTokenId>> {
+    // Parse the parameter.
+    let params: TokensByTierParams = ctx.parameter_cursor().get()?;
+
+    let tokens = match host.state().tier_tokens.get(&params.tier) {
+        Some(tokens) => tokens
+            .iter()
+            .skip(params.skip as usize)
+            .take(params.take as usize)
+            .map(|token_id| *token_id)
+            .collect(),
+        None => Vec::new(),
+    };
+    Ok(tokens)
+}
+
+/// Get the number of licenses ever minted to an account, including burned
+/// ones.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "mintCountOf",
+    parameter = "AccountAddress",
+    return_value = "u32",
+    error = "ContractError"
+)]
+fn contract_mint_count_of<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<u32> {
+    // Parse the parameter.
+    let account: AccountAddress = ctx.parameter_cursor().get()?;
+    Ok(host.state().mint_counts.get(&account).map(|count| *count).unwrap_or(0))
+}
+
+/// Get the number of distinct tokens each queried address holds, counting a
+/// fungible token once for any non-zero balance. Unlike `balanceOf`, this
+/// counts across all tokens.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "balanceCountOf",
+    parameter = "Vec<Address>",
+    return_value = "Vec<u32>",
+    error = "ContractError"
+)]
+fn contract_balance_count_of<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<Vec<u32>> {
+    // Parse the parameter.
+    let addresses: Vec<Address> = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    let counts = addresses
+        .iter()
+        .map(|address| state.owned_counts.get(address).map(|count| *count).unwrap_or(0))
+        .collect();
+    Ok(counts)
+}
+
+/// Get the number of distinct addresses holding at least one token.
+#[receive(
+    contract = "LicenseContract",
+    name = "holderCount",
+    return_value = "u64"
+)]
+fn contract_holder_count<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<u64> {
+    Ok(host.state().holder_count())
+}
+
+/// Mint new tokens with a given address as the owner of these tokens.
+/// Can only be called by a `Minter` or a global operator. Returns the token ID.
+/// Logs a `Mint` and a `TokenMetadata` event for each token.
+/// The url for the token metadata is the web3id appended on the configured
+/// metadata base URL. Without an explicit hash, the metadata hash is the
+/// checksum of the token ID and web3id, see `computeChecksum`.
+///
+/// It rejects if:
+/// - The sender is not a `Minter` or a global operator.
+/// - Fails to parse parameter.
+/// - The web3id is malformed.
+/// - The token ID is outside the range reserved for the sender.
+/// - Any of the tokens fails to be minted, which could be if:
+///     - The minted token ID already exists.
+///     - Fails to log Mint event
+///     - Fails to log TokenMetadata event
+///
+/// Note: Can at most mint 32 token types in one call due to the limit on the
+/// number of logs a smart contract can produce on each function call.
+#[receive(
+    contract = "LicenseContract",
+    name = "mint",
+    parameter = "MintParams",
+    return_value = "ContractTokenId",
+    error = "ContractError",
+    enable_logger,
+    crypto_primitives,
+    mutable
+)]
+fn contract_mint<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> ContractResult<ContractTokenId> {
+    // Get the sender of the transaction
+    let sender = ctx.sender();
+
+    let (state, builder) = host.state_and_builder();
+    ensure!(state.can_mint(&sender), ContractError::Unauthorized);
+    state.ensure_not_paused(PAUSE_MINT)?;
+
+    // Parse the parameter.
+    let params: MintParams = ctx.parameter_cursor().get()?;
+    let token_id = params.token;
+    let now = ctx.metadata().block_time();
+    mint_license(state, builder, logger, crypto_primitives, sender, now, params)?;
+    Ok(token_id)
+}
+
+/// Mint a license on behalf of `sender`, shared by `mint` and `mintNext`.
+/// The sender must already be authorized to mint.
+fn mint_license<S: HasStateApi>(
+    state: &mut State<S>,
+    builder: &mut StateBuilder<S>,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+    sender: Address,
+    now: Timestamp,
+    params: MintParams,
+) -> ContractResult<()> {
+    let token_id = params.token;
+    let web3id = params.web3id;
+
+    ensure!(check_web3id(&web3id), CustomContractError::InvalidWeb3Id.into());
+    state.ensure_in_reserved_range(&sender, &token_id)?;
+
+    let metadata = TokenMetadata {
+        url: build_web3id_metadata_url(&state.metadata_base_url, &web3id),
+        hash: Some(
+            params.hash.unwrap_or_else(|| compute_checksum(&token_id, &web3id, crypto_primitives)),
+        ),
+        content_type: params.content_type,
+        expiry: params.expiry,
+        additional_urls: Vec::new(),
+        issued_at: now,
+        tier: params.tier,
+    };
+
+    let token_owner: Address = Address::Account(params.owner);
+
+    // Mint the token in the state.
+    state.mint(token_id, metadata.clone(), &token_owner, now, builder)?;
+    let _ = state.web3ids.insert(token_id, web3id);
+
+    log_mint(logger, token_id, token_owner, &metadata)
+}
+
+/// The parameter for the contract function `mintNext` which mints a license
+/// with the next free token ID.
+#[derive(Serial, Deserial, SchemaType)]
+pub struct MintNextParams {
+    /// Owner of the newly minted token.
+    pub owner:  AccountAddress,
+    /// Web3Id
+    pub web3id: Web3Id,
+}
+
+/// Mint a license with the next free token ID from the counter in the state,
+/// so callers do not have to pick token IDs themselves. Returns the token ID.
+/// Logs a `Mint` and a `TokenMetadata` event.
+///
+/// It rejects if:
+/// - The sender is not a `Minter` or a global operator.
+/// - Fails to parse parameter.
+/// - The web3id is malformed.
+/// - The token ID is outside the range reserved for the sender.
+/// - The token IDs are exhausted, with `TokenIdSpaceExhausted`.
+/// - Fails to log events.
+#[receive(
+    contract = "LicenseContract",
+    name = "mintNext",
+    parameter = "MintNextParams",
+    return_value = "ContractTokenId",
+    error = "ContractError",
+    enable_logger,
+    crypto_primitives,
+    mutable
+)]
+fn contract_mint_next<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> ContractResult<ContractTokenId> {
+    // Get the sender of the transaction
+    let sender = ctx.sender();
+
+    let (state, builder) = host.state_and_builder();
+    ensure!(state.can_mint(&sender), ContractError::Unauthorized);
+    state.ensure_not_paused(PAUSE_MINT)?;
+
+    // Parse the parameter.
+    let MintNextParams { owner, web3id } = ctx.parameter_cursor().get()?;
+
+    let token_id = state.next_token_id()?;
+    let params = MintParams {
+        owner,
+        token: token_id,
+        web3id,
+        hash: None,
+        content_type: None,
+        expiry: None,
+        tier: 0,
+    };
+    let now = ctx.metadata().block_time();
+    mint_license(state, builder, logger, crypto_primitives, sender, now, params)?;
+    Ok(token_id)
+}
+
+/// The parameter for the contract function `mintWithMetadata` which mints a
+/// token with a custom metadata URL.
+#[derive(Serial, Deserial, SchemaType)]
+pub struct MintWithMetadataParams {
+    /// Owner of the newly minted token.
+    pub owner: AccountAddress,
+    /// The token to mint.
+    pub token: ContractTokenId,
+    /// The metadata URL, stored as is instead of being derived from the
+    /// metadata base URL.
+    #[concordium(size_length = 2)]
+    pub url:   String,
+    /// A optional SHA256 hash of the metadata.
+    pub hash:  Option<[u8; 32]>,
+}
+
+/// Mint a new token with a custom metadata URL, e.g. an IPFS CID or a URL on
+/// another host. Authorized like `mint`. Returns the token ID.
+/// Logs a `Mint` and a `TokenMetadata` event.
+///
+/// It rejects if:
+/// - The sender is not a `Minter` or a global operator.
+/// - Fails to parse parameter.
+/// - The URL is malformed.
+/// - The token ID is outside the range reserved for the sender.
+/// - The minted token ID already exists.
+/// - Fails to log events.
+#[receive(
+    contract = "LicenseContract",
+    name = "mintWithMetadata",
+    parameter = "MintWithMetadataParams",
+    return_value = "ContractTokenId",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_mint_with_metadata<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<ContractTokenId> {
+    // Get the sender of the transaction
+    let sender = ctx.sender();
+
+    let (state, builder) = host.state_and_builder();
+    ensure!(state.can_mint(&sender), ContractError::Unauthorized);
+    state.ensure_not_paused(PAUSE_MINT)?;
+
+    // Parse the parameter.
+    let params: MintWithMetadataParams = ctx.parameter_cursor().get()?;
+    ensure!(check_metadata_url(&params.url), CustomContractError::InvalidMetadataUrl.into());
+    state.ensure_in_reserved_range(&sender, &params.token)?;
+
+    let now = ctx.metadata().block_time();
+    let metadata = TokenMetadata {
+        url: params.url,
+        hash: params.hash,
+        content_type: None,
+        expiry: None,
+        additional_urls: Vec::new(),
+        issued_at: now,
+        tier: 0,
+    };
+    let token_owner = Address::Account(params.owner);
+    state.mint(params.token, metadata.clone(), &token_owner, now, builder)?;
+
+    log_mint(logger, params.token, token_owner, &metadata)?;
+    Ok(params.token)
+}
+
+/// Log the `Mint` and `TokenMetadata` events for a newly minted token.
+fn log_mint(
+    logger: &mut impl HasLogger,
+    token_id: ContractTokenId,
+    owner: Address,
+    metadata: &TokenMetadata,
+) -> ContractResult<()> {
+    // Event for minted NFT.
+    logger.log(&Cis2Event::Mint(MintEvent {
+        token_id,
+        amount: ContractTokenAmount::from(1),
+        owner,
+    }))?;
+
+    // Metadata URL for the NFT.
+    log_token_metadata(logger, token_id, metadata)
+}
+
+/// Log the `TokenMetadata` event of a token. Logged at mint and again
+/// whenever the stored metadata changes, so indexers stay up to date.
+fn log_token_metadata(
+    logger: &mut impl HasLogger,
+    token_id: ContractTokenId,
+    metadata: &TokenMetadata,
+) -> ContractResult<()> {
+    logger.log(&Cis2Event::TokenMetadata::<_, ContractTokenAmount>(
+        TokenMetadataEvent {
+            token_id,
+            metadata_url: MetadataUrl {
+                url: metadata.url.clone(),
+                hash: metadata.hash,
+            },
+        },
+    ))?;
+    Ok(())
+}
+
+/// The parameter for the contract function `mintBatch` which mints several
+/// tokens to a given address.
+#[derive(Serial, Deserial, SchemaType)]
+pub struct MintBatchParams {
+    /// Owner of the newly minted tokens.
+    pub owner: AccountAddress,
+    /// The tokens to mint with their Web3Id.
+    pub tokens: Vec<(ContractTokenId, Web3Id)>,
+}
+
+/// Mint several new tokens with a given address as the owner of these tokens.
+/// Can only be called by a `Minter` or a global operator. Returns the token IDs
+/// in the order of the parameter.
+/// Logs a `Mint` and a `TokenMetadata` event for each token.
+/// The metadata hash of each token is its checksum, see `computeChecksum`.
+/// The whole batch is validated before any token is minted.
+///
+/// It rejects if:
+/// - The sender is not a `Minter` or a global operator.
+/// - Fails to parse parameter.
+/// - The events of all the tokens do not fit in the log limit.
+/// - Any of the web3ids is malformed.
+/// - Any of the token IDs is outside the range reserved for the sender.
+/// - Any of the token IDs already exists or occurs twice in the batch.
+/// - The owner is not allowed to hold tokens or is frozen.
+#[receive(
+    contract = "LicenseContract",
+    name = "mintBatch",
+    parameter = "MintBatchParams",
+    return_value = "Vec<ContractTokenId>",
+    error = "ContractError",
+    enable_logger,
+    crypto_primitives,
+    mutable
+)]
+fn contract_mint_batch<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> ContractResult<Vec<ContractTokenId>> {
+    // Get the sender of the transaction
+    let sender = ctx.sender();
+
+    let (state, builder) = host.state_and_builder();
+    ensure!(state.can_mint(&sender), ContractError::Unauthorized);
+    state.ensure_not_paused(PAUSE_MINT)?;
+
+    // Parse the parameter.
+    let params: MintBatchParams = ctx.parameter_cursor().get()?;
+
+    // Every token logs a `Mint` and a `TokenMetadata` event.
+    ensure!(
+        params.tokens.len() * 2 <= MAX_LOG_EVENTS,
+        CustomContractError::LogFull.into()
+    );
+
+    let token_owner: Address = Address::Account(params.owner);
+    state.ensure_valid_mint_recipient(&token_owner)?;
+
+    // Validate the entire batch up front, so no token is minted if any fails.
+    let mut batch_ids = BTreeSet::new();
+    for (token_id, web3id) in params.tokens.iter() {
+        ensure!(check_web3id(web3id), CustomContractError::InvalidWeb3Id.into());
+        state.ensure_in_reserved_range(&sender, token_id)?;
+        ensure!(
+            batch_ids.insert(token_id.0) && !state.contains_token(token_id),
+            CustomContractError::TokenIdAlreadyExists.into()
+        );
+    }
+
+    let now = ctx.metadata().block_time();
+    let mut minted = Vec::with_capacity(params.tokens.len());
+    for (token_id, web3id) in params.tokens {
+        let metadata = TokenMetadata {
+            url: build_web3id_metadata_url(&state.metadata_base_url, &web3id),
+            hash: Some(compute_checksum(&token_id, &web3id, crypto_primitives)),
+            content_type: None,
+            expiry: None,
+            additional_urls: Vec::new(),
+            issued_at: now,
+            tier: 0,
+        };
+        // Mint the token in the state.
+        state.mint(token_id, metadata.clone(), &token_owner, now, builder)?;
+        let _ = state.web3ids.insert(token_id, web3id);
+        log_mint(logger, token_id, token_owner, &metadata)?;
+        minted.push(token_id);
+    }
+    Ok(minted)
+}
+
+/// Mint licenses directly to a list of recipients, e.g. for an airdrop,
+/// instead of minting them to a treasury and transferring them from there.
+/// Can only be called by the contract owner. Returns the token IDs in the
+/// order of the parameter.
+/// Logs a `Mint` and a `TokenMetadata` event for each token.
+/// The whole list is validated before any token is minted.
+///
+/// It rejects if:
+/// - Sender is not the owner of the contract instance.
+/// - Fails to parse parameter.
+/// - The events of all the tokens do not fit in the log limit.
+/// - Any of the web3ids is malformed.
+/// - Any of the token IDs is outside the range reserved for the sender.
+/// - Any of the token IDs already exists or occurs twice in the list.
+/// - Any of the recipients is not registered or is frozen.
+#[receive(
+    contract = "LicenseContract",
+    name = "distribute",
+    parameter = "Vec<(AccountAddress, ContractTokenId, Web3Id)>",
+    return_value = "Vec<ContractTokenId>",
+    error = "ContractError",
+    enable_logger,
+    crypto_primitives,
+    mutable
+)]
+fn contract_distribute<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> ContractResult<Vec<ContractTokenId>> {
+    // Get the sender of the transaction
+    let sender = ctx.sender();
+
+    let (state, builder) = host.state_and_builder();
+    ensure!(sender == state.owner, ContractError::Unauthorized);
+    state.ensure_not_paused(PAUSE_MINT)?;
+
+    // Parse the parameter.
+    let params: Vec<(AccountAddress, ContractTokenId, Web3Id)> = ctx.parameter_cursor().get()?;
+
+    // Every token logs a `Mint` and a `TokenMetadata` event.
+    ensure!(params.len() * 2 <= MAX_LOG_EVENTS, CustomContractError::LogFull.into());
+
+    // Validate the entire list up front, so no token is minted if any fails.
+    let mut batch_ids = BTreeSet::new();
+    for (owner, token_id, web3id) in params.iter() {
+        ensure!(check_web3id(web3id), CustomContractError::InvalidWeb3Id.into());
+        state.ensure_in_reserved_range(&sender, token_id)?;
+        ensure!(
+            batch_ids.insert(token_id.0) && !state.contains_token(token_id),
+            CustomContractError::TokenIdAlreadyExists.into()
+        );
+        state.ensure_valid_mint_recipient(&Address::Account(*owner))?;
+    }
+
+    let now = ctx.metadata().block_time();
+    let mut minted = Vec::with_capacity(params.len());
+    for (owner, token, web3id) in params {
+        mint_license(state, builder, logger, crypto_primitives, sender, now, MintParams {
+            owner,
+            token,
+            web3id,
+            hash: None,
+            content_type: None,
+            expiry: None,
+            tier: 0,
+        })?;
+        minted.push(token);
+    }
+    Ok(minted)
+}
+
+/// Mint an amount of a fungible license pool to a given address.
+/// Can only be called by a `Minter` or a global operator.
+/// The first mint of a token ID creates the pool and logs a `TokenMetadata`
+/// event, every mint logs a `Mint` event. Each mint counts once towards the
+/// mint cap of the recipient, whatever the amount.
+///
+/// It rejects if:
+/// - The sender is not a `Minter` or a global operator.
+/// - Fails to parse parameter.
+/// - The token ID is outside the range reserved for the sender.
+/// - The recipient is not a valid mint recipient, e.g. it is frozen.
+/// - The recipient already reached the mint cap, with `MintQuotaExceeded`.
+/// - The token ID already exists as a non-fungible token.
+/// - The total supply of the token would overflow.
+/// - Fails to log events.
+#[receive(
+    contract = "LicenseContract",
+    name = "mintFungible",
+    parameter = "MintFungibleParams",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_mint_fungible<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<()> {
+    // Get the sender of the transaction
+    let sender = ctx.sender();
+
+    let (state, builder) = host.state_and_builder();
+    ensure!(state.can_mint(&sender), ContractError::Unauthorized);
+    state.ensure_not_paused(PAUSE_MINT)?;
+
+    // Parse the parameter.
+    let params: MintFungibleParams = ctx.parameter_cursor().get()?;
+    state.ensure_in_reserved_range(&sender, &params.token)?;
+    let token_owner = Address::Account(params.owner);
+
+    let now = ctx.metadata().block_time();
+    let created =
+        state.mint_fungible(params.token, params.amount, &token_owner, now, builder)?;
+
+    logger.log(&Cis2Event::Mint(MintEvent {
+        token_id: params.token,
+        amount: params.amount,
+        owner: token_owner,
+    }))?;
+
+    if created {
+        logger.log(&Cis2Event::TokenMetadata::<_, ContractTokenAmount>(
+            TokenMetadataEvent {
+                token_id: params.token,
+                metadata_url: MetadataUrl {
+                    url: state.token_metadata_url(&params.token),
+                    hash: None,
+                },
+            },
+        ))?;
+    }
+    Ok(())
+}
+
+/// Get the current total supply of a token across all holders.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - The token does not exist.
+#[receive(
+    contract = "LicenseContract",
+    name = "tokenTotalSupply",
+    parameter = "ContractTokenId",
+    return_value = "ContractTokenAmount",
+    error = "ContractError"
+)]
+fn contract_token_total_supply<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<ContractTokenAmount> {
+    // Parse the parameter.
+    let token_id: ContractTokenId = ctx.parameter_cursor().get()?;
+    host.state().total_supply(&token_id)
+}
+
+/// Get the current supply of each queried token without rejecting unknown
+/// tokens: 1 for an existing license, the total supply for a fungible pool and
+/// 0 for tokens which were never minted or have been burned.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "supplyOf",
+    parameter = "Vec<ContractTokenId>",
+    return_value = "Vec<ContractTokenAmount>",
+    error = "ContractError"
+)]
+fn contract_supply_of<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<Vec<ContractTokenAmount>> {
+    // Parse the parameter.
+    let token_ids: Vec<ContractTokenId> = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    let supplies = token_ids
+        .iter()
+        .map(|token_id| state.total_supply(token_id).unwrap_or_else(|_| 0.into()))
+        .collect();
+    Ok(supplies)
+}
+
+/// Get the number of token IDs currently in existence.
+#[receive(
+    contract = "LicenseContract",
+    name = "totalSupply",
+    return_value = "u64"
+)]
+fn contract_total_supply<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<u64> {
+    Ok(host.state().token_count)
+}
+
+type TransferParameter = TransferParams<ContractTokenId, ContractTokenAmount>;
+
+/// Execute a list of token transfers, in the order of the list.
+///
+/// Logs a `Transfer` event and invokes a receive hook function for every
+/// transfer in the list, except for transfers of amount zero, which are no-ops.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - The `Transfer` events of all the transfers do not fit in the log limit,
+///   with `LogFull`. This is checked before any transfer is executed.
+/// - Any of the transfers fail to be executed, which could be if:
+///     - The `token_id` does not exist.
+///     - The sender is not the owner of the token, an operator of the `from`
+///       address, or an operator of the `from` address for this specific
+///       `token_id`.
+///     - The token is not owned by the `from`.
+///     - The `from` or `to` address is frozen.
+///     - The `to` address is the blackhole address, with `UseBurnInstead`.
+/// - Fails to log event.
+/// - Any of the receive hook function calls rejects, with `ReceiveHookFailed`.
+#[receive(
+    contract = "LicenseContract",
+    name = "transfer",
+    parameter = "TransferParameter",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_transfer<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let TransferParams(transfers): TransferParameter = ctx.parameter_cursor().get()?;
+    // Get the sender who invoked this contract function.
+    let sender = ctx.sender();
+    transfer_tokens(host, logger, sender, ctx.metadata().block_time(), transfers)
+}
+
+/// Execute a list of token transfers on behalf of `sender`, shared by
+/// `transfer` and `permit`.
+fn transfer_tokens<S: HasStateApi>(
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    sender: Address,
+    now: Timestamp,
+    transfers: Vec<Transfer<ContractTokenId, ContractTokenAmount>>,
+) -> ContractResult<()> {
+    host.state().ensure_not_paused(PAUSE_TRANSFER)?;
+    // Every transfer of a non-zero amount logs a `Transfer` event. Receive
+    // hooks log in the receiving contract, so they do not count here.
+    let logged = transfers.iter().filter(|transfer| transfer.amount != 0.into()).count();
+    ensure!(logged <= MAX_LOG_EVENTS, CustomContractError::LogFull.into());
+    // The owner and admins can move tokens during their lockup.
+    let bypasses_lockup =
+        sender == host.state().owner || host.state().has_role(&sender, Role::Admin);
+
+    for Transfer {
+        token_id,
+        amount,
+        from,
+        to,
+        data,
+    } in transfers
+    {
+        let (state, builder) = host.state_and_builder();
+
+        // Authenticate the sender for this transfer
+        ensure!(
+            from == sender
+                || state.is_operator(&sender, &from)
+                || state.is_scoped_operator(&sender, &from, &token_id),
+            ContractError::Unauthorized
+        );
+        state.ensure_token_not_paused(&token_id)?;
+        if !bypasses_lockup {
+            state.ensure_lockup_elapsed(&token_id, now)?;
+        }
+
+        let to_address = to.address();
+        state.ensure_not_frozen(&from)?;
+        state.ensure_not_frozen(&to_address)?;
+        state.ensure_not_blackhole(&to_address)?;
+
+        // Update the contract state
+        state.transfer(&token_id, amount, &from, &to_address, builder)?;
+
+        // A zero transfer does not move a token, so it is a no-op without an
+        // event or receive hook call.
+        if amount == 0.into() {
+            continue;
+        }
+
+        // Log transfer event
+        logger.log(&Cis2Event::Transfer(TransferEvent {
+            token_id,
+            amount,
+            from,
+            to: to_address,
+        }))?;
+
+        // If the receiver is a contract: invoke the receive hook function.
+        if let Receiver::Contract(address, function) = to {
+            let parameter = OnReceivingCis2Params {
+                token_id,
+                amount,
+                from,
+                data,
+            };
+            host.invoke_contract(
+                &address,
+                &parameter,
+                function.as_entrypoint_name(),
+                Amount::zero(),
+            )
+            .map_err(|_| CustomContractError::ReceiveHookFailed)?;
+        }
+    }
+    Ok(())
+}
+
+/// The contract function invoked by `transferAndCall` after the transfer.
+#[derive(Serialize, SchemaType)]
+pub struct TransferCallback {
+    /// The contract to invoke.
+    pub address:    ContractAddress,
+    /// The entrypoint to invoke.
+    pub entrypoint: OwnedEntrypointName,
+    /// The parameter to invoke the entrypoint with.
+    pub parameter:  OwnedParameter,
+}
+
+/// The parameter type for the contract function `transferAndCall`.
+#[derive(Serialize, SchemaType)]
+pub struct TransferAndCallParams {
+    /// The transfer to execute.
+    pub transfer: Transfer<ContractTokenId, ContractTokenAmount>,
+    /// The function to invoke once the transfer is executed.
+    pub callback: TransferCallback,
+}
+
+/// Execute a transfer and then invoke a callback in a single atomic step. The
+/// callback is invoked after the state is updated, for any kind of receiver,
+/// and the transfer is undone if the callback rejects.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - The transfer fails to be executed, for the same reasons as `transfer`.
+/// - The callback rejects or cannot be invoked, with `CallbackFailed`.
+#[receive(
+    contract = "LicenseContract",
+    name = "transferAndCall",
+    parameter = "TransferAndCallParams",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_transfer_and_call<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let TransferAndCallParams {
+        transfer,
+        callback,
+    } = ctx.parameter_cursor().get()?;
+    let sender = ctx.sender();
+    transfer_tokens(host, logger, sender, ctx.metadata().block_time(), vec![transfer])?;
+
+    // Rejecting here rolls back the transfer together with its events.
+    host.invoke_contract_raw(
+        &callback.address,
+        callback.parameter.as_parameter(),
+        callback.entrypoint.as_entrypoint_name(),
+        Amount::zero(),
+    )
+    .map_err(|_| CustomContractError::CallbackFailed)?;
+    Ok(())
+}
+
+/// Receive an NFT of another CIS-2 contract and hold it in escrow for the
+/// address which sent it, until it is released with `withdrawEscrow`.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - Sender is not a contract.
+/// - Escrow is disabled, or the amount is not exactly one token.
+#[receive(
+    contract = "LicenseContract",
+    name = "onReceivingCIS2",
+    parameter = "OnReceivingCis2Params<TokenIdVec, ContractTokenAmount>",
+    error = "ContractError",
+    mutable
+)]
+fn contract_on_receiving_cis2<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let params: OnReceivingCis2Params<TokenIdVec, ContractTokenAmount> =
+        ctx.parameter_cursor().get()?;
+
+    // Only the token contract itself can report the tokens it transferred.
+    let token_contract = match ctx.sender() {
+        Address::Contract(address) => address,
+        Address::Account(_) => bail!(ContractError::Unauthorized),
+    };
+
+    let state = host.state_mut();
+    ensure!(
+        state.escrow_enabled && params.amount == 1.into(),
+        CustomContractError::EscrowRejected.into()
+    );
+    let _ = state.escrowed.insert((token_contract, params.token_id), params.from);
+    Ok(())
+}
+
+/// The parameter type for the contract function `withdrawEscrow`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct WithdrawEscrowParams {
+    /// The contract of the escrowed token.
+    pub contract: ContractAddress,
+    /// The escrowed token.
+    pub token_id: TokenIdVec,
+    /// The receiver of the token.
+    pub to:       Receiver,
+}
+
+/// Release a token held in escrow by transferring it to the given receiver.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - The token is not held in escrow.
+/// - Sender is neither the address which sent the token nor has the `Admin`
+///   role.
+/// - The transfer on the token contract fails.
+#[receive(
+    contract = "LicenseContract",
+    name = "withdrawEscrow",
+    parameter = "WithdrawEscrowParams",
+    error = "ContractError",
+    mutable
+)]
+fn contract_withdraw_escrow<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let WithdrawEscrowParams {
+        contract,
+        token_id,
+        to,
+    } = ctx.parameter_cursor().get()?;
+
+    let sender = ctx.sender();
+    let state = host.state_mut();
+    let key = (contract, token_id);
+    let holder = state
+        .escrowed
+        .get(&key)
+        .map(|holder| *holder)
+        .ok_or(ContractError::from(CustomContractError::NotEscrowed))?;
+    ensure!(
+        sender == holder || state.has_role(&sender, Role::Admin),
+        ContractError::Unauthorized
+    );
+    // Release the holding before the transfer, so it cannot be withdrawn twice.
+    state.escrowed.remove(&key);
+
+    let transfer = Transfer {
+        token_id: key.1,
+        amount: ContractTokenAmount::from(1),
+        from: Address::Contract(ctx.self_address()),
+        to,
+        data: AdditionalData::empty(),
+    };
+    host.invoke_contract(
+        &contract,
+        &TransferParams(vec![transfer]),
+        EntrypointName::new_unchecked("transfer"),
+        Amount::zero(),
+    )
+    .map_err(CustomContractError::from)?;
+    Ok(())
+}
+
+/// Get the address a token of another contract is held in escrow for, if it
+/// is held in escrow.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "escrowHolder",
+    parameter = "(ContractAddress, TokenIdVec)",
+    return_value = "Option<Address>",
+    error = "ContractError"
+)]
+fn contract_escrow_holder<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<Option<Address>> {
+    // Parse the parameter.
+    let key: (ContractAddress, TokenIdVec) = ctx.parameter_cursor().get()?;
+
+    Ok(host.state().escrowed.get(&key).map(|holder| *holder))
+}
+
+/// The operators of an owner as returned by `operatorGraph`.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct OperatorGraph {
+    /// The operators allowed to act on all tokens of the owner.
+    pub operators: Vec<Address>,
+    /// The operators allowed to transfer only some tokens of the owner, with
+    /// those tokens.
+    pub scoped:    Vec<(Address, Vec<ContractTokenId>)>,
+}
+
+/// Get all operators of an owner in one call.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "operatorGraph",
+    parameter = "Address",
+    return_value = "OperatorGraph",
+    error = "ContractError"
+)]
+fn contract_operator_graph<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<OperatorGraph> {
+    // Parse the parameter.
+    let owner: Address = ctx.parameter_cursor().get()?;
+
+    let state = host.state();
+    let mut operators: Vec<Address> = state
+        .state
+        .get(&owner)
+        .map(|address_state| address_state.operators.iter().map(|x| *x).collect())
+        .unwrap_or_default();
+    operators.sort();
+
+    // Group the scoped approvals of the owner by operator.
+    let mut by_operator: BTreeMap<Address, Vec<ContractTokenId>> = BTreeMap::new();
+    for (key, scoped_operators) in state.scoped_operators.iter() {
+        if key.0 != owner {
+            continue;
+        }
+        for scoped_operator in scoped_operators.iter() {
+            by_operator.entry(*scoped_operator).or_default().push(key.1);
+        }
+    }
+    let scoped: Vec<(Address, Vec<ContractTokenId>)> = by_operator
+        .into_iter()
+        .map(|(operator, mut tokens)| {
+            tokens.sort_by_key(|token_id| token_id.0);
+            (operator, tokens)
+        })
+        .collect();
+    Ok(OperatorGraph {
+        operators,
+        scoped,
+    })
+}
+
+/// The parameter type for the contract function `updateScopedOperator`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct UpdateScopedOperatorParams {
+    /// Whether the operator is added or removed.
+    pub update:   OperatorUpdate,
+    /// The operator.
+    pub operator: Address,
+    /// The token of the sender the operator may transfer.
+    pub token_id: ContractTokenId,
+}
+
+/// Enable or disable an address as an operator of the sender address for a
+/// single token, e.g. for a marketplace listing. Operators for all tokens,
+/// added with `updateOperator`, are not affected.
+/// Logs a `ScopedOperatorUpdated` event.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - Updating operators is paused.
+/// - The update adds the sender as its own operator.
+/// - Fails to log event.
+#[receive(
+    contract = "LicenseContract",
+    name = "updateScopedOperator",
+    parameter = "UpdateScopedOperatorParams",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_update_scoped_operator<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let UpdateScopedOperatorParams {
+        update,
+        operator,
+        token_id,
+    } = ctx.parameter_cursor().get()?;
+
+    let owner = ctx.sender();
+    let (state, builder) = host.state_and_builder();
+    state.ensure_not_paused(PAUSE_UPDATE_OPERATOR)?;
+    ensure!(
+        update == OperatorUpdate::Remove || operator != owner,
+        CustomContractError::SelfOperator.into()
+    );
+
+    match update {
+        OperatorUpdate::Add => {
+            state
+                .scoped_operators
+                .entry((owner, token_id))
+                .or_insert_with(|| builder.new_set())
+                .modify(|operators| operators.insert(operator));
+        }
+        OperatorUpdate::Remove => {
+            state.scoped_operators.entry((owner, token_id)).and_modify(|operators| {
+                operators.remove(&operator);
+            });
+        }
+    }
+
+    logger.log(&CustomEvent::ScopedOperatorUpdated(ScopedOperatorUpdatedEvent {
+        owner,
+        operator,
+        token_id,
+        update,
+    }))?;
+    Ok(())
+}
+
+/// Enable or disable addresses as operators of the sender address.
+/// Logs an `UpdateOperator` event for each update.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - The events of all the updates do not fit in the log limit.
+/// - Any of the updates adds the sender as its own operator, with
+///   `SelfOperator`.
+/// - Fails to log event.
+#[receive(
+    contract = "LicenseContract",
+    name = "updateOperator",
+    parameter = "UpdateOperatorParams",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_update_operator<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let UpdateOperatorParams(params) = ctx.parameter_cursor().get()?;
+    // Get the sender who invoked this contract function.
+    let sender = ctx.sender();
+    update_operators(host, logger, sender, params)
+}
+
+/// Apply a list of operator updates for `sender`, shared by `updateOperator`
+/// and `permit`.
+fn update_operators<S: HasStateApi>(
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    sender: Address,
+    params: Vec<UpdateOperator>,
+) -> ContractResult<()> {
+    let (state, builder) = host.state_and_builder();
+    state.ensure_not_paused(PAUSE_UPDATE_OPERATOR)?;
+    // Check the log budget up front, so no update is applied if it is exceeded.
+    ensure!(params.len() <= MAX_LOG_EVENTS, CustomContractError::LogFull.into());
+    for param in params {
+        // An address is always allowed to act on its own tokens, so adding it as
+        // its own operator is a client bug.
+        ensure!(
+            param.update == OperatorUpdate::Remove || param.operator != sender,
+            CustomContractError::SelfOperator.into()
+        );
+        // Update the operator in the state.
+        match param.update {
+            OperatorUpdate::Add => state.add_operator(&sender, &param.operator, builder),
+            OperatorUpdate::Remove => state.remove_operator(&sender, &param.operator),
+        }
+
+        // Log the appropriate event
+        logger.log(
+            &Cis2Event::<ContractTokenId, ContractTokenAmount>::UpdateOperator(
+                UpdateOperatorEvent {
+                    owner: sender,
+                    operator: param.operator,
+                    update: param.update,
+                },
+            ),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Get the global operators, which can mint tokens without the `Minter` role.
+#[receive(
+    contract = "LicenseContract",
+    name = "globalOperators",
+    return_value = "Vec<Address>"
+)]
+fn contract_global_operators<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<Vec<Address>> {
+    let mut operators: Vec<Address> = host.state().operators.iter().map(|x| *x).collect();
+    operators.sort();
+    Ok(operators)
+}
+
+/// Add a global operator, which can mint tokens without the `Minter` role.
+/// Logs a `GlobalOperatorUpdated` event.
+///
+/// It rejects if:
+/// - Sender is not the owner of the contract instance.
+/// - It fails to parse the parameter.
+/// - Fails to log event.
+#[receive(
+    contract = "LicenseContract",
+    name = "addGlobalOperator",
+    parameter = "Address",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_add_global_operator<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let operator: Address = ctx.parameter_cursor().get()?;
+
+    let state = host.state_mut();
+    ensure!(ctx.sender() == state.owner, ContractError::Unauthorized);
+    state.add_global_operator(&operator);
+
+    logger.log(&CustomEvent::GlobalOperatorUpdated(GlobalOperatorUpdatedEvent {
+        operator,
+        update: OperatorUpdate::Add,
+    }))?;
+    Ok(())
+}
+
+/// Remove a global operator.
+/// Logs a `GlobalOperatorUpdated` event.
+///
+/// It rejects if:
+/// - Sender is not the owner of the contract instance.
+/// - It fails to parse the parameter.
+/// - Fails to log event.
+#[receive(
+    contract = "LicenseContract",
+    name = "removeGlobalOperator",
+    parameter = "Address",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_remove_global_operator<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let operator: Address = ctx.parameter_cursor().get()?;
+
+    let state = host.state_mut();
+    ensure!(ctx.sender() == state.owner, ContractError::Unauthorized);
+    state.remove_global_operator(&operator);
+
+    logger.log(&CustomEvent::GlobalOperatorUpdated(GlobalOperatorUpdatedEvent {
+        operator,
+        update: OperatorUpdate::Remove,
+    }))?;
+    Ok(())
+}
+
+/// Takes a list of queries. Each query is an owner address and some address to
+/// check as an operator of the owner address.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "operatorOf",
+    parameter = "OperatorOfQueryParams",
+    return_value = "OperatorOfQueryResponse",
+    error = "ContractError"
+)]
+fn contract_operator_of<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<OperatorOfQueryResponse> {
+    // Parse the parameter.
+    let params: OperatorOfQueryParams = ctx.parameter_cursor().get()?;
+    // Build the response.
+    let mut response = Vec::with_capacity(params.queries.len());
+    for query in params.queries {
+        // Query the state for address being an operator of owner.
+        let is_operator = host.state().is_operator(&query.address, &query.owner);
+        response.push(is_operator);
+    }
+    let result = OperatorOfQueryResponse::from(response);
+    Ok(result)
+}
+
+/// The message signed by an account to let a sponsor invoke an entrypoint on
+/// its behalf, following CIS-3.
+#[derive(Debug, Serialize, SchemaType, Clone)]
+pub struct PermitMessage {
+    /// The contract the message is intended for.
+    pub contract_address: ContractAddress,
+    /// The nonce of the signer, preventing replays.
+    pub nonce:            u64,
+    /// The time until which the message can be executed.
+    pub timestamp:        Timestamp,
+    /// The entrypoint to invoke, `transfer` or `updateOperator`.
+    pub entry_point:      OwnedEntrypointName,
+    /// The serialized parameter of the entrypoint.
+    #[concordium(size_length = 2)]
+    pub payload:          Vec<u8>,
+}
+
+/// The parameter type for the contract functions `permit` and
+/// `viewMessageHash`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct PermitParams {
+    /// The signatures of the signer on the message hash.
+    pub signature: AccountSignatures,
+    /// The account which signed the message.
+    pub signer:    AccountAddress,
+    /// The signed message.
+    pub message:   PermitMessage,
+}
+
+/// The parameter type for the contract function `nonceOf`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct NonceOfQueryParams {
+    /// The accounts to get the current nonce of.
+    pub queries: Vec<AccountAddress>,
+}
+
+/// Calculate the hash an account signs to authorize a `PermitMessage` or an
+/// `UpdateOperatorSignedMessage`: the SHA256 of the signer address, 8 zero
+/// bytes and the serialized message, as done by the wallets when signing
+/// arbitrary messages.
+fn calculate_message_hash(
+    signer: &AccountAddress,
+    message: &impl Serial,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> [u8; 32] {
+    let mut bytes = Vec::from(signer.0);
+    bytes.extend_from_slice(&[0u8; 8]);
+    bytes.extend(to_bytes(message));
+    crypto_primitives.hash_sha2_256(&bytes).0
+}
+
+/// Execute a `transfer` or `updateOperator` on behalf of the signer of a
+/// message, so a sponsor can pay for the transaction. Consumes the nonce of
+/// the signer and logs a `SponsoredExecuted` event besides the events of the
+/// executed entrypoint.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - `permit` is paused.
+/// - The nonce is not the current nonce of the signer.
+/// - The message is intended for another contract or has expired.
+/// - The signature is invalid.
+/// - The entrypoint is neither `transfer` nor `updateOperator`, or its
+///   parameter fails to parse.
+/// - The executed entrypoint rejects.
+#[receive(
+    contract = "LicenseContract",
+    name = "permit",
+    parameter = "PermitParams",
+    error = "ContractError",
+    crypto_primitives,
+    enable_logger,
+    mutable
+)]
+fn contract_permit<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let PermitParams { signature, signer, message } = ctx.parameter_cursor().get()?;
+
+    host.state().ensure_not_paused(PAUSE_PERMIT)?;
+    // Consume the nonce of the signer.
+    let nonce = host.state_mut().consume_nonce(signer);
+    ensure_eq!(message.nonce, nonce, CustomContractError::NonceMismatch.into());
+    ensure_eq!(
+        message.contract_address,
+        ctx.self_address(),
+        CustomContractError::WrongContract.into()
+    );
+    let now = ctx.metadata().block_time();
+    ensure!(message.timestamp > now, CustomContractError::Expired.into());
+
+    let message_hash = calculate_message_hash(&signer, &message, crypto_primitives);
+    let valid_signature = host
+        .check_account_signature(signer, &signature, &message_hash)
+        .map_err(CustomContractError::from)?;
+    ensure!(valid_signature, CustomContractError::WrongSignature.into());
+
+    let sender = Address::Account(signer);
+    let entry_point = message.entry_point.as_entrypoint_name();
+    let action = if entry_point == EntrypointName::new_unchecked("transfer") {
+        let TransferParams(transfers): TransferParameter = from_bytes(&message.payload)?;
+        transfer_tokens(host, logger, sender, now, transfers)?;
+        SponsoredAction::Transfer
+    } else if entry_point == EntrypointName::new_unchecked("updateOperator") {
+        let UpdateOperatorParams(updates) = from_bytes(&message.payload)?;
+        update_operators(host, logger, sender, updates)?;
+        SponsoredAction::UpdateOperator
+    } else {
+        bail!(CustomContractError::WrongEntryPoint.into())
+    };
+
+    logger.log(&CustomEvent::SponsoredExecuted(SponsoredExecutedEvent {
+        signer,
+        nonce,
+        action,
+    }))?;
+    Ok(())
+}
+
+/// The parameter type for the contract function `supportsPermit`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct SupportsPermitQueryParams {
+    /// The entrypoints to check.
+    #[concordium(size_length = 2)]
+    pub queries: Vec<OwnedEntrypointName>,
+}
+
+/// Check which of the given entrypoints can be invoked through `permit`, as
+/// required by CIS-3. None can while `permit` is paused.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "supportsPermit",
+    parameter = "SupportsPermitQueryParams",
+    return_value = "SupportsQueryResponse",
+    error = "ContractError"
+)]
+fn contract_supports_permit<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<SupportsQueryResponse> {
+    // Parse the parameter.
+    let params: SupportsPermitQueryParams = ctx.parameter_cursor().get()?;
+
+    let enabled = host.state().ensure_not_paused(PAUSE_PERMIT).is_ok();
+    let results: Vec<SupportResult> = params
+        .queries
+        .iter()
+        .map(|entry_point| {
+            let entry_point = entry_point.as_entrypoint_name();
+            if enabled
+                && PERMIT_ENTRYPOINTS
+                    .iter()
+                    .any(|name| entry_point == EntrypointName::new_unchecked(name))
+            {
+                SupportResult::Support
+            } else {
+                SupportResult::NoSupport
+            }
+        })
+        .collect();
+    Ok(SupportsQueryResponse::from(results))
+}
+
+/// Get the message hash an account has to sign for `permit`. The signature in
+/// the parameter is ignored.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "viewMessageHash",
+    parameter = "PermitParams",
+    return_value = "[u8; 32]",
+    error = "ContractError",
+    crypto_primitives
+)]
+fn contract_view_message_hash<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    _host: &impl HasHost<State<S>, StateApiType = S>,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> ContractResult<[u8; 32]> {
+    // Parse the parameter.
+    let params: PermitParams = ctx.parameter_cursor().get()?;
+    Ok(calculate_message_hash(&params.signer, &params.message, crypto_primitives))
+}
+
+/// The message signed by an account to update its operators through
+/// `updateOperatorSigned`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct UpdateOperatorSignedMessage {
+    /// The contract the message is intended for.
+    pub contract_address: ContractAddress,
+    /// The nonce of the signer, preventing replays.
+    pub nonce:            u64,
+    /// The time until which the message can be executed.
+    pub timestamp:        Timestamp,
+    /// The operator updates to apply for the signer.
+    pub updates:          UpdateOperatorParams,
+}
+
+/// The parameter type for the contract functions `updateOperatorSigned` and
+/// `viewOperatorMessageHash`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct UpdateOperatorSignedParams {
+    /// The signatures of the signer on the message hash.
+    pub signature: AccountSignatures,
+    /// The account which signed the message.
+    pub signer:    AccountAddress,
+    /// The signed message.
+    pub message:   UpdateOperatorSignedMessage,
+}
+
+/// Apply operator updates signed by an account, so a sponsor can pay for the
+/// transaction. Unlike `permit` the updates are part of the signed message
+/// itself rather than an opaque payload. Consumes the nonce of the signer,
+/// shared with `permit`, and logs a `SponsoredExecuted` event besides the
+/// `UpdateOperator` events.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - The nonce is not the current nonce of the signer, with `NonceMismatch`.
+/// - The message is intended for another contract, with `WrongContract`.
+/// - The message has expired, with `Expired`.
+/// - The signature is invalid, with `WrongSignature`.
+/// - Any of the updates rejects, for the same reasons as `updateOperator`.
+#[receive(
+    contract = "LicenseContract",
+    name = "updateOperatorSigned",
+    parameter = "UpdateOperatorSignedParams",
+    error = "ContractError",
+    crypto_primitives,
+    enable_logger,
+    mutable
+)]
+fn contract_update_operator_signed<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let UpdateOperatorSignedParams { signature, signer, message } =
+        ctx.parameter_cursor().get()?;
+
+    // Consume the nonce of the signer.
+    let nonce = host.state_mut().consume_nonce(signer);
+    ensure_eq!(message.nonce, nonce, CustomContractError::NonceMismatch.into());
+    ensure_eq!(
+        message.contract_address,
+        ctx.self_address(),
+        CustomContractError::WrongContract.into()
+    );
+    ensure!(message.timestamp > ctx.metadata().block_time(), CustomContractError::Expired.into());
+
+    let message_hash = calculate_message_hash(&signer, &message, crypto_primitives);
+    let valid_signature = host
+        .check_account_signature(signer, &signature, &message_hash)
+        .map_err(CustomContractError::from)?;
+    ensure!(valid_signature, CustomContractError::WrongSignature.into());
+
+    let UpdateOperatorParams(updates) = message.updates;
+    update_operators(host, logger, Address::Account(signer), updates)?;
+
+    logger.log(&CustomEvent::SponsoredExecuted(SponsoredExecutedEvent {
+        signer,
+        nonce,
+        action: SponsoredAction::UpdateOperator,
+    }))?;
+    Ok(())
+}
+
+/// Get the message hash an account has to sign for `updateOperatorSigned`.
+/// The signature in the parameter is ignored.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "viewOperatorMessageHash",
+    parameter = "UpdateOperatorSignedParams",
+    return_value = "[u8; 32]",
+    error = "ContractError",
+    crypto_primitives
+)]
+fn contract_view_operator_message_hash<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    _host: &impl HasHost<State<S>, StateApiType = S>,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> ContractResult<[u8; 32]> {
+    // Parse the parameter.
+    let params: UpdateOperatorSignedParams = ctx.parameter_cursor().get()?;
+    Ok(calculate_message_hash(&params.signer, &params.message, crypto_primitives))
+}
+
+/// Get the nonce each queried account has to use in its next signed message.
+/// Accounts which never signed a message have the nonce 0.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "nonceOf",
+    parameter = "NonceOfQueryParams",
+    return_value = "Vec<u64>",
+    error = "ContractError"
+)]
+fn contract_nonce_of<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<Vec<u64>> {
+    // Parse the parameter.
+    let params: NonceOfQueryParams = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    let nonces = params
+        .queries
+        .iter()
+        .map(|account| state.nonces.get(account).map(|nonce| *nonce).unwrap_or(0))
+        .collect();
+    Ok(nonces)
+}
+
+/// Parameter type for the CIS-2 function `balanceOf` specialized to the subset
+/// of TokenIDs used by this contract.
+type ContractBalanceOfQueryParams = BalanceOfQueryParams<ContractTokenId>;
+/// Response type for the CIS-2 function `balanceOf` specialized to the subset
+/// of TokenAmounts used by this contract.
+type ContractBalanceOfQueryResponse = BalanceOfQueryResponse<ContractTokenAmount>;
+
+/// Get the balance of given token IDs and addresses.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - Any of the queried `token_id` does not exist.
+#[receive(
+    contract = "LicenseContract",
+    name = "balanceOf",
+    parameter = "ContractBalanceOfQueryParams",
+    return_value = "ContractBalanceOfQueryResponse",
+    error = "ContractError"
+)]
+fn contract_balance_of<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<ContractBalanceOfQueryResponse> {
+    // Parse the parameter.
+    let params: ContractBalanceOfQueryParams = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    // Fetch the state of each queried address only once, batches often query
+    // many tokens of the same address.
+    let mut address_states = BTreeMap::new();
+    // Build the response.
+    let mut response = Vec::with_capacity(params.queries.len());
+    for query in params.queries {
+        let address_state = address_states
+            .entry(query.address)
+            .or_insert_with(|| state.state.get(&query.address));
+        // Query the state for balance.
+        let amount =
+            state.balance_in(&query.token_id, &query.address, address_state.as_deref())?;
+        response.push(amount);
+    }
+    let result = ContractBalanceOfQueryResponse::from(response);
+    Ok(result)
+}
+
+/// Get the balance of given token IDs and addresses like `balanceOf`, but
+/// with a balance of 0 for token IDs which do not exist, instead of rejecting
+/// the whole query. Meant for best-effort dashboards.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "balanceOfLenient",
+    parameter = "ContractBalanceOfQueryParams",
+    return_value = "ContractBalanceOfQueryResponse",
+    error = "ContractError"
+)]
+fn contract_balance_of_lenient<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<ContractBalanceOfQueryResponse> {
+    // Parse the parameter.
+    let params: ContractBalanceOfQueryParams = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    // Fetch the state of each queried address only once, see `balanceOf`.
+    let mut address_states = BTreeMap::new();
+    // Build the response.
+    let mut response = Vec::with_capacity(params.queries.len());
+    for query in params.queries {
+        if !state.contains_token(&query.token_id) {
+            response.push(0.into());
+            continue;
+        }
+        let address_state = address_states
+            .entry(query.address)
+            .or_insert_with(|| state.state.get(&query.address));
+        let amount =
+            state.balance_in(&query.token_id, &query.address, address_state.as_deref())?;
+        response.push(amount);
+    }
+    Ok(ContractBalanceOfQueryResponse::from(response))
+}
+
+/// Parameter type for the CIS-2 function `tokenMetadata` specialized to the
+/// subset of TokenIDs used by this contract.
+type ContractTokenMetadataQueryParams = TokenMetadataQueryParams<ContractTokenId>;
+
+/// Get the token metadata URLs and checksums given a list of token IDs.
+/// Existing tokens without stored metadata, e.g. after a partial migration,
+/// get the URL built from the base URL without a checksum.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - Any of the queried `token_id` does not exist.
+#[receive(
+    contract = "LicenseContract",
+    name = "tokenMetadata",
+    parameter = "ContractTokenMetadataQueryParams",
+    return_value = "TokenMetadataQueryResponse",
+    error = "ContractError"
+)]
+fn contract_token_metadata<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<TokenMetadataQueryResponse> {
+    // Parse the parameter.
+    let params: ContractTokenMetadataQueryParams = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    // Build the response.
+    let mut response = Vec::with_capacity(params.queries.len());
+    for token_id in params.queries {
+        response.push(state.metadata_url_of(&token_id)?);
+    }
+    let result = TokenMetadataQueryResponse::from(response);
+    Ok(result)
+}
+
+/// Get the metadata URL and checksum of a single token, like `tokenMetadata`
+/// does for a list of token IDs.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - The `token_id` does not exist.
+#[receive(
+    contract = "LicenseContract",
+    name = "metadataUrlOf",
+    parameter = "ContractTokenId",
+    return_value = "MetadataUrl",
+    error = "ContractError"
+)]
+fn contract_metadata_url_of<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<MetadataUrl> {
+    // Parse the parameter.
+    let token_id: ContractTokenId = ctx.parameter_cursor().get()?;
+    host.state().metadata_url_of(&token_id)
+}
+
+/// The parameter type for the contract function `tokensOf`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct TokensOfParams {
+    /// The address to list the tokens of.
+    pub address: Address,
+    /// The number of tokens to skip.
+    pub start:   u32,
+    /// The maximum number of tokens to return.
+    pub limit:   u32,
+}
+
+/// The return type of the contract function `tokensOf`.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct TokensOfResponse {
+    /// The page of token IDs.
+    pub tokens:   Vec<ContractTokenId>,
+    /// Whether more tokens follow this page.
+    pub has_more: bool,
+}
+
+/// List the non-fungible tokens owned by an address, one page at a time.
+/// Since the iteration order of the underlying set is unspecified, the tokens
+/// are sorted ascending by their `u32` value before paging, so consecutive
+/// pages are consistent as long as the holdings do not change in between.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "tokensOf",
+    parameter = "TokensOfParams",
+    return_value = "TokensOfResponse",
+    error = "ContractError"
+)]
+fn contract_tokens_of<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<TokensOfResponse> {
+    // Parse the parameter.
+    let params: TokensOfParams = ctx.parameter_cursor().get()?;
+
+    let mut owned: Vec<ContractTokenId> = host
+        .state()
+        .state
+        .get(&params.address)
+        .map(|address_state| address_state.owned_tokens.iter().map(|x| *x).collect())
+        .unwrap_or_default();
+    owned.sort_by_key(|token_id| token_id.0);
+
+    let start = (params.start as usize).min(owned.len());
+    let end = start.saturating_add(params.limit as usize).min(owned.len());
+    Ok(TokensOfResponse {
+        tokens:   owned[start..end].to_vec(),
+        has_more: end < owned.len(),
+    })
+}
+
+/// The parameter type for the contract function `tokenOwner`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct TokenOwnerQueryParams {
+    /// The tokens to look up the holder of.
+    pub queries: Vec<ContractTokenId>,
+}
+
+/// Get the current holder of each queried token. `None` means the token does
+/// not exist, or is a fungible token without a single holder.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "tokenOwner",
+    parameter = "TokenOwnerQueryParams",
+    return_value = "Vec<Option<Address>>",
+    error = "ContractError"
+)]
+fn contract_token_owner<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<Vec<Option<Address>>> {
+    // Parse the parameter.
+    let params: TokenOwnerQueryParams = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    Ok(params.queries.iter().map(|token_id| state.owner_of(token_id)).collect())
+}
+
+/// Check whether each queried license is currently valid, i.e. it exists and
+/// has not expired at the current block time. Expired licenses can still be
+/// transferred, e.g. to return them, so this is a separate check.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "isValid",
+    parameter = "Vec<ContractTokenId>",
+    return_value = "Vec<bool>",
+    error = "ContractError"
+)]
+fn contract_is_valid<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<Vec<bool>> {
+    // Parse the parameter.
+    let token_ids: Vec<ContractTokenId> = ctx.parameter_cursor().get()?;
+    let now = ctx.metadata().block_time();
+    let state = host.state();
+
+    let response = token_ids
+        .iter()
+        .map(|token_id| {
+            state
+                .metadata
+                .get(token_id)
+                .is_some_and(|metadata| metadata.expiry.filter(|expiry| *expiry < now).is_none())
+        })
+        .collect();
+    Ok(response)
+}
+
+/// The validity of a license as returned by `isValidBatch`.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub enum ValidityStatus {
+    /// The license exists and has not expired.
+    Valid,
+    /// The license exists but has expired.
+    Expired,
+    /// The license was never minted, or was burned without being revoked.
+    Nonexistent,
+    /// The license was burned with the reason `Revoked`.
+    Revoked,
+}
+
+/// Get the validity of each queried license at the current block time, telling
+/// apart expired, revoked and unknown licenses in one call.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "isValidBatch",
+    parameter = "Vec<ContractTokenId>",
+    return_value = "Vec<ValidityStatus>",
+    error = "ContractError"
+)]
+fn contract_is_valid_batch<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<Vec<ValidityStatus>> {
+    // Parse the parameter.
+    let token_ids: Vec<ContractTokenId> = ctx.parameter_cursor().get()?;
+    let now = ctx.metadata().block_time();
+    let state = host.state();
+
+    let response = token_ids
+        .iter()
+        .map(|token_id| {
+            if state.revoked.contains(token_id) {
+                return ValidityStatus::Revoked;
+            }
+            if !state.contains_token(token_id) {
+                return ValidityStatus::Nonexistent;
+            }
+            let expired = state
+                .metadata
+                .get(token_id)
+                .and_then(|metadata| metadata.expiry)
+                .is_some_and(|expiry| expiry < now);
+            if expired {
+                ValidityStatus::Expired
+            } else {
+                ValidityStatus::Valid
+            }
+        })
+        .collect();
+    Ok(response)
+}
+
+/// Get the web3id a license was minted for.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - The license does not exist or was minted without a web3id.
+#[receive(
+    contract = "LicenseContract",
+    name = "web3IdOf",
+    parameter = "ContractTokenId",
+    return_value = "Web3Id",
+    error = "ContractError"
+)]
+fn contract_web3id_of<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<Web3Id> {
+    // Parse the parameter.
+    let token_id: ContractTokenId = ctx.parameter_cursor().get()?;
+    let web3id = host
+        .state()
+        .web3ids
+        .get(&token_id)
+        .map(|web3id| web3id.clone())
+        .ok_or(CustomContractError::LicenseNotFound)?;
+    Ok(web3id)
+}
+
+/// The parameter type for the contract function `computeChecksum`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct ComputeChecksumParams {
+    /// The token ID of the license.
+    pub token_id: ContractTokenId,
+    /// The web3id of the license.
+    pub web3id:   Web3Id,
+}
+
+/// Compute the checksum stored as the metadata hash of licenses minted with a
+/// web3id and without an explicit hash, so clients can verify it locally.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "computeChecksum",
+    parameter = "ComputeChecksumParams",
+    return_value = "[u8; 32]",
+    error = "ContractError",
+    crypto_primitives
+)]
+fn contract_compute_checksum<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    _host: &impl HasHost<State<S>, StateApiType = S>,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> ContractResult<[u8; 32]> {
+    // Parse the parameter.
+    let ComputeChecksumParams { token_id, web3id } = ctx.parameter_cursor().get()?;
+    Ok(compute_checksum(&token_id, &web3id, crypto_primitives))
+}
+
+/// The parameter type for the contract function `verifyMetadataHash`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct VerifyMetadataHashParams {
+    /// The token to check the metadata hash of.
+    pub token_id: ContractTokenId,
+    /// The hash the metadata is expected to have.
+    pub expected: [u8; 32],
+}
+
+/// Check that the stored metadata hash of a token matches the expected hash,
+/// so contracts receiving a license can trust its metadata without fetching
+/// it. Tokens minted without a hash never match.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - The token does not exist.
+#[receive(
+    contract = "LicenseContract",
+    name = "verifyMetadataHash",
+    parameter = "VerifyMetadataHashParams",
+    return_value = "bool",
+    error = "ContractError"
+)]
+fn contract_verify_metadata_hash<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<bool> {
+    // Parse the parameter.
+    let VerifyMetadataHashParams { token_id, expected } = ctx.parameter_cursor().get()?;
+    let metadata = host.state().metadata.get(&token_id).ok_or(ContractError::InvalidTokenId)?;
+    Ok(metadata.hash == Some(expected))
+}
+
+/// The parameter type for the contract function `setRoyalty`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct SetRoyaltyParams {
+    /// The token to override the royalty of, `None` to set the default.
+    pub token_id:     Option<ContractTokenId>,
+    /// The address receiving the royalties.
+    pub receiver:     Address,
+    /// The royalty rate in basis points of the sale amount.
+    pub basis_points: u16,
+}
+
+/// Set the default royalty on resales, or override it for a single token.
+///
+/// It rejects if:
+/// - Sender is not the owner of the contract instance.
+/// - It fails to parse the parameter.
+/// - The rate exceeds 10000 basis points.
+/// - The token to override the royalty of does not exist.
+#[receive(
+    contract = "LicenseContract",
+    name = "setRoyalty",
+    parameter = "SetRoyaltyParams",
+    error = "ContractError",
+    mutable
+)]
+fn contract_set_royalty<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let SetRoyaltyParams { token_id, receiver, basis_points } = ctx.parameter_cursor().get()?;
+
+    let state = host.state_mut();
+    ensure!(ctx.sender() == state.owner, ContractError::Unauthorized);
+    ensure!(
+        basis_points <= MAX_ROYALTY_BASIS_POINTS,
+        CustomContractError::InvalidRoyalty.into()
+    );
+
+    match token_id {
+        Some(token_id) => {
+            ensure!(state.contains_token(&token_id), ContractError::InvalidTokenId);
+            let _ = state.token_royalties.insert(token_id, (receiver, basis_points));
+        }
+        None => state.default_royalty = (receiver, basis_points),
+    }
+    Ok(())
+}
+
+/// The parameter type for the contract function `royaltyInfo`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct RoyaltyInfoParams {
+    /// The token being sold.
+    pub token_id:    ContractTokenId,
+    /// The price the token is sold for.
+    pub sale_amount: Amount,
+}
+
+/// The return type of the contract function `royaltyInfo`.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct RoyaltyInfo {
+    /// The address receiving the royalty.
+    pub receiver:       Address,
+    /// The royalty owed on the sale.
+    pub royalty_amount: Amount,
+}
+
+/// Get the royalty owed on a resale of a token, using the override of the
+/// token if it has one and the default royalty otherwise.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - The token does not exist.
+#[receive(
+    contract = "LicenseContract",
+    name = "royaltyInfo",
+    parameter = "RoyaltyInfoParams",
+    return_value = "RoyaltyInfo",
+    error = "ContractError"
+)]
+fn contract_royalty_info<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<RoyaltyInfo> {
+    // Parse the parameter.
+    let RoyaltyInfoParams { token_id, sale_amount } = ctx.parameter_cursor().get()?;
+
+    let state = host.state();
+    ensure!(state.contains_token(&token_id), ContractError::InvalidTokenId);
+    let (receiver, basis_points) = state
+        .token_royalties
+        .get(&token_id)
+        .map(|royalty| *royalty)
+        .unwrap_or(state.default_royalty);
+
+    // The rate is at most 10000 basis points, so the royalty fits in a u64.
+    let royalty = u128::from(sale_amount.micro_ccd) * u128::from(basis_points)
+        / u128::from(MAX_ROYALTY_BASIS_POINTS);
+    Ok(RoyaltyInfo {
+        receiver,
+        royalty_amount: Amount::from_micro_ccd(royalty as u64),
+    })
+}
+
+/// The parameter type for the contract function `previewMetadataUrl`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct PreviewMetadataUrlParams {
+    /// The token to preview the metadata URL of.
+    pub token_id: ContractTokenId,
+    /// The web3id a license would be minted for with `mint`, or `None` for a
+    /// token without web3id, i.e. a fungible pool.
+    pub web3id:   Option<Web3Id>,
+}
+
+/// Get the metadata URL a token would be minted with, without requiring the
+/// token to exist.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - The web3id is malformed.
+#[receive(
+    contract = "LicenseContract",
+    name = "previewMetadataUrl",
+    parameter = "PreviewMetadataUrlParams",
+    return_value = "String",
+    error = "ContractError"
+)]
+fn contract_preview_metadata_url<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<String> {
+    // Parse the parameter.
+    let PreviewMetadataUrlParams { token_id, web3id } = ctx.parameter_cursor().get()?;
+
+    let state = host.state();
+    match web3id {
+        Some(web3id) => {
+            ensure!(check_web3id(&web3id), CustomContractError::InvalidWeb3Id.into());
+            Ok(build_web3id_metadata_url(&state.metadata_base_url, &web3id))
+        }
+        None => Ok(state.token_metadata_url(&token_id)),
+    }
+}
+
+/// Get the full stored metadata, including the content type, given a list of
+/// token IDs. The CIS2 `tokenMetadata` only returns the URL and hash.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - Any of the queried `token_id` does not exist.
+#[receive(
+    contract = "LicenseContract",
+    name = "tokenMetadataExt",
+    parameter = "ContractTokenMetadataQueryParams",
+    return_value = "Vec<TokenMetadata>",
+    error = "ContractError"
+)]
+fn contract_token_metadata_ext<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<Vec<TokenMetadata>> {
+    // Parse the parameter.
+    let params: ContractTokenMetadataQueryParams = ctx.parameter_cursor().get()?;
+    // Build the response.
+    let mut response = Vec::with_capacity(params.queries.len());
+    for token_id in params.queries {
+        let metadata = host
+            .state()
+            .metadata
+            .get(&token_id)
+            .map(|metadata| metadata.clone())
+            .ok_or(ContractError::InvalidTokenId)?;
+        response.push(metadata);
+    }
+    Ok(response)
+}
+
+/// The parameter type for the contract function `updateTokenMetadata`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct UpdateTokenMetadataParams {
+    /// The token to update the metadata of.
+    pub token_id: ContractTokenId,
+    /// The new URL following the specification RFC1738.
+    #[concordium(size_length = 2)]
+    pub url:      String,
+    /// The new optional SHA256 hash of the content.
+    pub hash:     Option<[u8; 32]>,
+}
+
+/// Replace the metadata URL and hash of a token, e.g. after the metadata
+/// backend moved. Logs a `TokenMetadata` event so indexers pick up the change.
+///
+/// It rejects if:
+/// - Sender is neither the owner of the contract instance nor an `Admin`.
+/// - It fails to parse the parameter.
+/// - The token does not exist.
+/// - Fails to log event.
+#[receive(
+    contract = "LicenseContract",
+    name = "updateTokenMetadata",
+    parameter = "UpdateTokenMetadataParams",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_update_token_metadata<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let UpdateTokenMetadataParams { token_id, url, hash } = ctx.parameter_cursor().get()?;
+
+    let sender = ctx.sender();
+    let state = host.state_mut();
+    ensure!(
+        sender == state.owner || state.has_role(&sender, Role::Admin),
+        ContractError::Unauthorized
+    );
+
+    let mut metadata = state.metadata.get_mut(&token_id).ok_or(ContractError::InvalidTokenId)?;
+    metadata.url = url;
+    metadata.hash = hash;
+
+    log_token_metadata(logger, token_id, &metadata)
+}
+
+/// The parameter type for the contract function `setAdditionalMetadataUrls`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct SetAdditionalMetadataUrlsParams {
+    /// The token to set the additional URLs of.
+    pub token_id: ContractTokenId,
+    /// The additional URLs, replacing the current ones.
+    #[concordium(size_length = 2)]
+    pub urls:     Vec<(MetadataRole, String)>,
+}
+
+/// Replace the additional metadata URLs of a token. The primary URL returned
+/// by `tokenMetadata` is not affected.
+///
+/// It rejects if:
+/// - Sender is neither the owner of the contract instance nor an `Admin`.
+/// - It fails to parse the parameter.
+/// - The token does not exist.
+#[receive(
+    contract = "LicenseContract",
+    name = "setAdditionalMetadataUrls",
+    parameter = "SetAdditionalMetadataUrlsParams",
+    error = "ContractError",
+    mutable
+)]
+fn contract_set_additional_metadata_urls<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let SetAdditionalMetadataUrlsParams { token_id, urls } = ctx.parameter_cursor().get()?;
+
+    let sender = ctx.sender();
+    let state = host.state_mut();
+    ensure!(
+        sender == state.owner || state.has_role(&sender, Role::Admin),
+        ContractError::Unauthorized
+    );
+
+    let mut metadata = state.metadata.get_mut(&token_id).ok_or(ContractError::InvalidTokenId)?;
+    metadata.additional_urls = urls;
+    Ok(())
+}
+
+/// The metadata URLs of a token as returned by `tokenMetadataExtended`.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct TokenMetadataExtended {
+    /// The primary URL, as returned by `tokenMetadata`.
+    #[concordium(size_length = 2)]
+    pub url:             String,
+    /// The additional URLs with their role.
+    #[concordium(size_length = 2)]
+    pub additional_urls: Vec<(MetadataRole, String)>,
+}
+
+/// Get the primary and additional metadata URLs, given a list of token IDs.
+/// The CIS2 `tokenMetadata` only returns the primary URL, for wallets.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - Any of the queried `token_id` does not exist.
+#[receive(
+    contract = "LicenseContract",
+    name = "tokenMetadataExtended",
+    parameter = "ContractTokenMetadataQueryParams",
+    return_value = "Vec<TokenMetadataExtended>",
+    error = "ContractError"
+)]
+fn contract_token_metadata_extended<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<Vec<TokenMetadataExtended>> {
+    // Parse the parameter.
+    let params: ContractTokenMetadataQueryParams = ctx.parameter_cursor().get()?;
+    // Build the response.
+    let mut response = Vec::with_capacity(params.queries.len());
+    for token_id in params.queries {
+        let metadata =
+            host.state().metadata.get(&token_id).ok_or(ContractError::InvalidTokenId)?;
+        response.push(TokenMetadataExtended {
+            url:             metadata.url.clone(),
+            additional_urls: metadata.additional_urls.clone(),
+        });
+    }
+    Ok(response)
+}
+
+/// The parameter type for the contract function `renew`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct RenewParams {
+    /// The license to renew.
+    pub token_id:   ContractTokenId,
+    /// The new expiry of the license.
+    pub new_expiry: Timestamp,
+}
+
+/// Set a new expiry on an existing license, keeping its token ID.
+/// Logs a `TokenMetadata` and a `LicenseRenewed` event.
+///
+/// It rejects if:
+/// - Sender is neither the owner of the contract instance, an `Admin` nor a
+///   `Minter`.
+/// - It fails to parse the parameter.
+/// - The license does not exist.
+/// - Fails to log event.
+#[receive(
+    contract = "LicenseContract",
+    name = "renew",
+    parameter = "RenewParams",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_renew<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let RenewParams { token_id, new_expiry } = ctx.parameter_cursor().get()?;
+
+    let state = host.state_mut();
+    ensure!(state.can_set_expiry(&ctx.sender()), ContractError::Unauthorized);
+
+    {
+        let mut metadata =
+            state.metadata.get_mut(&token_id).ok_or(CustomContractError::LicenseNotFound)?;
+        metadata.expiry = Some(new_expiry);
+        log_token_metadata(logger, token_id, &metadata)?;
+    }
+
+    logger.log(&CustomEvent::LicenseRenewed(LicenseRenewedEvent {
+        token_id,
+        new_expiry,
+    }))?;
+    Ok(())
+}
+
+/// Set a new expiry on many existing licenses at once, e.g. after the term of
+/// a subscription tier changed. Logs a `LicenseRenewed` event for each
+/// license. If any license is missing, none of them is updated.
+///
+/// It rejects if:
+/// - Sender is neither the owner of the contract instance, an `Admin` nor a
+///   `Minter`, like for `renew`.
+/// - It fails to parse the parameter.
+/// - The events of all the licenses do not fit in the log limit.
+/// - Any of the licenses does not exist.
+/// - Fails to log events.
+#[receive(
+    contract = "LicenseContract",
+    name = "setExpiryBatch",
+    parameter = "Vec<(ContractTokenId, Timestamp)>",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_set_expiry_batch<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let updates: Vec<(ContractTokenId, Timestamp)> = ctx.parameter_cursor().get()?;
+
+    let state = host.state_mut();
+    ensure!(state.can_set_expiry(&ctx.sender()), ContractError::Unauthorized);
+    ensure!(updates.len() <= MAX_LOG_EVENTS, CustomContractError::LogFull.into());
+
+    for (token_id, new_expiry) in updates {
+        {
+            let mut metadata =
+                state.metadata.get_mut(&token_id).ok_or(CustomContractError::LicenseNotFound)?;
+            metadata.expiry = Some(new_expiry);
+        }
+        logger.log(&CustomEvent::LicenseRenewed(LicenseRenewedEvent {
+            token_id,
+            new_expiry,
+        }))?;
+    }
+    Ok(())
+}
+
+/// The parameter type for the contract function `revokeAndReissue`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct RevokeAndReissueParams {
+    /// The compromised license to burn.
+    pub old_token_id: ContractTokenId,
+    /// The token ID of the replacement license.
+    pub new_token_id: ContractTokenId,
+    /// The web3id of the replacement license.
+    pub web3id:       Web3Id,
+}
+
+/// Burn a compromised license and mint a replacement with a fresh token ID to
+/// the same holder in one transaction. The replacement keeps the content type,
+/// expiry, tier and additional URLs of the old license, and is minted even if
+/// the holder reached the mint cap or is frozen.
+/// Logs a `Burn` and a `LicenseRevoked` event for the old token, and a `Mint`
+/// and a `TokenMetadata` event for the new token.
+///
+/// It rejects if:
+/// - Sender is neither the owner of the contract instance nor an `Admin`.
+/// - It fails to parse the parameter.
+/// - Burning or minting is paused.
+/// - The old license does not exist.
+/// - The web3id is malformed.
+/// - The new token ID already exists.
+/// - Fails to log events.
+#[receive(
+    contract = "LicenseContract",
+    name = "revokeAndReissue",
+    parameter = "RevokeAndReissueParams",
+    error = "ContractError",
+    enable_logger,
+    crypto_primitives,
+    mutable
+)]
+fn contract_revoke_and_reissue<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let RevokeAndReissueParams {
+        old_token_id,
+        new_token_id,
+        web3id,
+    } = ctx.parameter_cursor().get()?;
+
+    let sender = ctx.sender();
+    let now = ctx.metadata().block_time();
+    let (state, builder) = host.state_and_builder();
+    ensure!(
+        sender == state.owner || state.has_role(&sender, Role::Admin),
+        ContractError::Unauthorized
+    );
+    state.ensure_not_paused(PAUSE_BURN)?;
+    state.ensure_not_paused(PAUSE_MINT)?;
+    ensure!(check_web3id(&web3id), CustomContractError::InvalidWeb3Id.into());
+
+    let url = build_web3id_metadata_url(&state.metadata_base_url, &web3id);
+    let hash = compute_checksum(&new_token_id, &web3id, crypto_primitives);
+    let (owner, metadata) = state.reissue(&old_token_id, new_token_id, url, hash, now, builder)?;
+    let _ = state.web3ids.insert(new_token_id, web3id);
+
+    logger.log(&Cis2Event::Burn(BurnEvent {
+        token_id: old_token_id,
+        amount: ContractTokenAmount::from(1),
+        owner,
+    }))?;
+    logger.log(&CustomEvent::LicenseRevoked(LicenseRevokedEvent {
+        token_id: old_token_id,
+        owner,
+        reason: BurnReason::Revoked,
+    }))?;
+
+    log_mint(logger, new_token_id, owner, &metadata)
+}
+
+/// Ownership and expiry of a license as returned by `gateInfo`.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct GateInfo {
+    /// The holder of the license, `None` for fungible tokens.
+    pub owner:   Option<Address>,
+    /// The time the license expires, if it expires at all.
+    pub expiry:  Option<Timestamp>,
+    /// Whether the license has expired at the current block time.
+    pub expired: bool,
+}
+
+/// Get the holder and expiry of a list of licenses in one call, for gateways
+/// validating many licenses at once.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - Any of the queried `token_id` does not exist.
+#[receive(
+    contract = "LicenseContract",
+    name = "gateInfo",
+    parameter = "Vec<ContractTokenId>",
+    return_value = "Vec<GateInfo>",
+    error = "ContractError"
+)]
+fn contract_gate_info<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<Vec<GateInfo>> {
+    // Parse the parameter.
+    let token_ids: Vec<ContractTokenId> = ctx.parameter_cursor().get()?;
+    let now = ctx.metadata().block_time();
+    let state = host.state();
+
+    let mut response = Vec::with_capacity(token_ids.len());
+    for token_id in token_ids {
+        let expiry = state
+            .metadata
+            .get(&token_id)
+            .map(|metadata| metadata.expiry)
+            .ok_or(ContractError::InvalidTokenId)?;
+        response.push(GateInfo {
+            owner: state.owner_of(&token_id),
+            expiry,
+            expired: expiry.is_some_and(|expiry| expiry < now),
+        });
+    }
+    Ok(response)
+}
+
+/// Get the supported standards or addresses for a implementation given list of
+/// standard identifiers.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "supports",
+    parameter = "SupportsQueryParams",
+    return_value = "SupportsQueryResponse",
+    error = "ContractError"
+)]
+fn contract_supports<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<SupportsQueryResponse> {
+    // Parse the parameter.
+    let params: SupportsQueryParams = ctx.parameter_cursor().get()?;
+
+    let state = host.state();
+    // Look up the implementors of each distinct standard only once, duplicate
+    // queries reuse the result.
+    let mut looked_up: Vec<(StandardIdentifierOwned, Option<Vec<ContractAddress>>)> =
+        Vec::new();
+    // Build the response, in the order of the queries.
+    let mut response = Vec::with_capacity(params.queries.len());
+    for std_id in params.queries {
+        if state.supports_natively(&std_id.as_standard_identifier()) {
+            response.push(SupportResult::Support);
+            continue;
+        }
+        let index = match looked_up.iter().position(|(id, _)| *id == std_id) {
+            Some(index) => index,
+            None => {
+                let implementors = state.implementors_of(&std_id);
+                looked_up.push((std_id, implementors));
+                looked_up.len() - 1
+            }
+        };
+        response.push(match &looked_up[index].1 {
+            Some(addresses) => SupportResult::SupportBy(addresses.clone()),
+            None => SupportResult::NoSupport,
+        });
+    }
+    let result = SupportsQueryResponse::from(response);
+    Ok(result)
+}
+
+/// Set the addresses for an implementation given a standard identifier and a
+/// list of contract addresses.
+///
+/// It rejects if:
+/// - Sender is not the owner of the contract instance.
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "setImplementors",
+    parameter = "SetImplementorsParams",
+    error = "ContractError",
+    mutable
+)]
+fn contract_set_implementor<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
+    // Authorize the sender against the stored owner.
+    ensure!(
+        ctx.sender() == host.state().owner,
+        CustomContractError::Unauthorized.into()
+    );
+    // Parse the parameter.
+    let params: SetImplementorsParams = ctx.parameter_cursor().get()?;
+    // Update the implementors in the state
+    host.state_mut()
+        .set_implementors(params.id, params.implementors);
+    Ok(())
+}
+
+/// The parameter type for the contract function `upgrade`.
+/// Takes the new module and optionally a migration function to call in the new
+/// module after the upgrade.
+#[derive(Serialize, SchemaType)]
+pub struct UpgradeParams {
+    /// The new module reference.
+    pub module:  ModuleReference,
+    /// Optional entrypoint to call in the new module after upgrade.
+    pub migrate: Option<(OwnedEntrypointName, OwnedParameter)>,
+}
+
+/// High-value operations which must be queued with `queueOperation` and can
+/// only be executed once `TIMELOCK_DELAY` has passed.
+#[derive(Serialize, SchemaType)]
+pub enum TimelockOperation {
+    /// Upgrade the contract to a new module.
+    Upgrade(UpgradeParams),
+}
+
+/// Queue a timelocked operation. The operation is identified by the SHA256
+/// hash of its serialization and can be executed with `executeOperation`
+/// once `TIMELOCK_DELAY` has passed. Returns the operation hash.
+///
+/// It rejects if:
+/// - Sender is not the owner of the contract instance.
+/// - It fails to parse the parameter.
+/// - The same operation is already queued.
+#[receive(
+    contract = "LicenseContract",
+    name = "queueOperation",
+    parameter = "TimelockOperation",
+    return_value = "HashSha2256",
+    error = "ContractError",
+    crypto_primitives,
+    mutable
+)]
+fn contract_queue_operation<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> ContractResult<HashSha2256> {
+    // Parse the parameter.
+    let operation: TimelockOperation = ctx.parameter_cursor().get()?;
+
+    let state = host.state_mut();
+    ensure!(ctx.sender() == state.owner, ContractError::Unauthorized);
+
+    let operation_hash = crypto_primitives.hash_sha2_256(&to_bytes(&operation));
+    let earliest_execution = ctx
+        .metadata()
+        .block_time()
+        .checked_add(TIMELOCK_DELAY)
+        .unwrap_abort();
+    state.queue_operation(operation_hash, earliest_execution)?;
+    Ok(operation_hash)
+}
+
+/// A queued timelocked operation as returned by `pendingOperations`.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct PendingOperation {
+    /// The SHA256 hash of the serialized operation.
+    pub operation_hash:     HashSha2256,
+    /// The earliest time the operation can be executed.
+    pub earliest_execution: Timestamp,
+}
+
+/// Get all queued timelocked operations with their earliest execution time.
+#[receive(
+    contract = "LicenseContract",
+    name = "pendingOperations",
+    return_value = "Vec<PendingOperation>"
+)]
+fn contract_pending_operations<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<Vec<PendingOperation>> {
+    let pending = host
+        .state()
+        .queued_operations
+        .iter()
+        .map(|(operation_hash, earliest_execution)| PendingOperation {
+            operation_hash:     *operation_hash,
+            earliest_execution: *earliest_execution,
+        })
+        .collect();
+    Ok(pending)
+}
+
+/// Execute a queued timelocked operation once its delay has passed.
+///
+/// It rejects if:
+/// - Sender is not the owner of the contract instance.
+/// - It fails to parse the parameter.
+/// - The operation was not queued or its delay has not passed yet.
+/// - The operation itself fails.
+#[receive(
+    contract = "LicenseContract",
+    name = "executeOperation",
+    parameter = "TimelockOperation",
+    crypto_primitives,
+    enable_logger,
+    low_level
+)]
+fn contract_execute_operation(
+    ctx: &ReceiveContext,
+    host: &mut LowLevelHost,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> ReceiveResult<()> {
+    // Parse the parameter.
+    let operation: TimelockOperation = ctx.parameter_cursor().get()?;
+    execute_operation(ctx, host, logger, crypto_primitives, operation)
+}
+
+/// Upgrade the contract. A shorthand for `executeOperation` with a
+/// `TimelockOperation::Upgrade`, which must have been queued beforehand.
+#[receive(
+    contract = "LicenseContract",
+    name = "upgrade",
+    parameter = "UpgradeParams",
+    crypto_primitives,
+    enable_logger,
+    low_level
+)]
+fn contract_upgrade(
+    ctx: &ReceiveContext,
+    host: &mut LowLevelHost,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+) -> ReceiveResult<()> {
+    // Parse the parameter.
+    let params: UpgradeParams = ctx.parameter_cursor().get()?;
+    execute_operation(ctx, host, logger, crypto_primitives, TimelockOperation::Upgrade(params))
+}
+
+/// Consume a matured queued operation and execute it.
+/// The low level host is used since an upgrade and migration can change the
+/// state layout, which must not be overwritten by the old layout afterwards.
+///
+/// Unauthorized senders are rejected rather than silently succeeding, so a
+/// successful transaction always means the operation was executed.
+///
+/// An upgrade compares the `STATE_VERSION` of the new module, queried through
+/// its `moduleStateVersion` entrypoint, with the state version before the
+/// upgrade. If they differ a migration entrypoint is required. The migration
+/// is invoked by the contract itself right after the upgrade and must
+/// - reject unless the sender is the contract itself,
+/// - read the state in the layout of the old version, using the low level
+///   host as the layout differs from the `State` of the new module,
+/// - write it in the new layout with `state_version` set to the new version.
+///
+/// After the migration the state version reported by `stateVersion` must
+/// match the version of the new module, otherwise the whole upgrade rejects.
+fn execute_operation(
+    ctx: &ReceiveContext,
+    host: &mut LowLevelHost,
+    logger: &mut impl HasLogger,
+    crypto_primitives: &impl HasCryptoPrimitives,
+    operation: TimelockOperation,
+) -> ReceiveResult<()> {
+    // Maps write through to the state directly, so the root does not need to
+    // be written back after consuming the queued operation.
+    let mut state: State<StateApi> = host.state().read_root()?;
+    ensure!(ctx.sender() == state.owner, ContractError::Unauthorized.into());
+
+    let operation_hash = crypto_primitives.hash_sha2_256(&to_bytes(&operation));
+    state.take_matured_operation(&operation_hash, ctx.metadata().block_time())?;
+
+    match operation {
+        TimelockOperation::Upgrade(params) => {
+            // Trigger the upgrade.
+            host.upgrade(params.module)?;
+            let module_version = query_state_version(host, ctx, "moduleStateVersion")?;
+            ensure!(
+                module_version == state.state_version || params.migrate.is_some(),
+                ContractError::from(CustomContractError::MigrationRequired).into()
+            );
+            // Call the migration function if provided.
+            if let Some((func, parameters)) = params.migrate {
+                host.invoke_contract_raw(
+                    &ctx.self_address(),
+                    parameters.as_parameter(),
+                    func.as_entrypoint_name(),
+                    Amount::zero(),
+                )?;
+            }
+            ensure!(
+                query_state_version(host, ctx, "stateVersion")? == module_version,
+                ContractError::from(CustomContractError::StateVersionMismatch).into()
+            );
+            logger.log(&CustomEvent::Upgraded(UpgradedEvent {
+                module: params.module,
+            }))?;
+        }
+    }
+    Ok(())
+}
+
+/// Invoke an entrypoint of this contract returning a state version.
+fn query_state_version(
+    host: &mut LowLevelHost,
+    ctx: &ReceiveContext,
+    entrypoint: &str,
+) -> ReceiveResult<u16> {
+    let (_, response) = host.invoke_contract_raw(
+        &ctx.self_address(),
+        Parameter::empty(),
+        EntrypointName::new_unchecked(entrypoint),
+        Amount::zero(),
+    )?;
+    let mut response =
+        response.ok_or(ContractError::from(CustomContractError::InvokeContractError))?;
+    Ok(response.get()?)
+}
+
+/// Get the `STATE_VERSION` of this module. The state is not read, so the new
+/// module can be queried during an upgrade before its state is migrated.
+#[receive(
+    contract = "LicenseContract",
+    name = "moduleStateVersion",
+    return_value = "u16",
+    low_level
+)]
+fn contract_module_state_version(
+    _ctx: &ReceiveContext,
+    _host: &mut LowLevelHost,
+) -> ReceiveResult<u16> {
+    Ok(STATE_VERSION)
+}
+
+/// Get the version of the current state layout.
+#[receive(
+    contract = "LicenseContract",
+    name = "stateVersion",
+    return_value = "u16"
+)]
+fn contract_state_version<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<u16> {
+    Ok(host.state().state_version)
+}
+
+/// Migrate the state of an older version to the state version of this module,
+/// following the migration contract described on `execute_operation`. There
+/// have been no layout changes yet, so only the version is updated.
+///
+/// It rejects if:
+/// - Sender is not the contract instance itself.
+/// - The state has a newer version than this module.
+#[receive(
+    contract = "LicenseContract",
+    name = "migrate",
+    error = "ContractError",
+    mutable
+)]
+fn contract_migrate<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
+    ensure!(
+        ctx.sender() == Address::Contract(ctx.self_address()),
+        ContractError::Unauthorized
+    );
+    let state = host.state_mut();
+    ensure!(
+        state.state_version <= STATE_VERSION,
+        CustomContractError::StateVersionMismatch.into()
+    );
+    state.state_version = STATE_VERSION;
+    Ok(())
+}
+
+/// Transfer the ownership of the contract instance to a new address. The roles
+/// of the current owner move to the new owner.
+/// Logs a `RoleRevoked` and a `RoleGranted` event for each role of the owner
+/// and an `OwnershipTransferred` event.
+///
+/// It rejects if:
+/// - Sender is not the current owner of the contract instance.
+/// - It fails to parse the parameter.
+/// - The new owner is a contract and `allow_contract_owner` is not set.
+/// - Fails to log event.
+#[receive(
+    contract = "LicenseContract",
+    name = "transferOwnership",
+    parameter = "TransferOwnershipParams",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_transfer_ownership<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let TransferOwnershipParams {
+        new_owner,
+        allow_contract_owner,
+    } = ctx.parameter_cursor().get()?;
+
+    let (state, builder) = host.state_and_builder();
+    // Only the current owner can hand over the contract.
+    ensure!(ctx.sender() == state.owner, ContractError::Unauthorized);
+    ensure!(
+        allow_contract_owner || matches!(new_owner, Address::Account(_)),
+        CustomContractError::InvalidOwner.into()
+    );
+
+    let previous_owner = state.owner;
+    if new_owner != previous_owner {
+        for role in ALL_ROLES {
+            if state.has_role(&previous_owner, role) {
+                state.revoke_role(&previous_owner, role);
+                logger.log(&CustomEvent::RoleRevoked(RoleEvent {
+                    address: previous_owner,
+                    role,
+                }))?;
+                state.grant_role(&new_owner, role, builder);
+                logger.log(&CustomEvent::RoleGranted(RoleEvent {
+                    address: new_owner,
+                    role,
+                }))?;
+            }
+        }
+    }
+    state.owner = new_owner;
+
+    logger.log(&CustomEvent::OwnershipTransferred(OwnershipTransferredEvent {
+        previous_owner,
+        new_owner,
+    }))?;
+    Ok(())
+}
+
+/// The parameter type for the contract function `renounceOwnership`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct RenounceOwnershipParams {
+    /// Must be `RENOUNCE_CONFIRMATION`.
+    pub confirmation: String,
+}
+
+/// Renounce the ownership of the contract instance for good, making the
+/// owner-gated entrypoints unusable. The owner becomes `RENOUNCED_OWNER` and
+/// loses its roles and global operator status; roles granted to other
+/// addresses are kept.
+/// Logs a `RoleRevoked` event for each role of the owner, a
+/// `GlobalOperatorUpdated` event if the owner was a global operator and an
+/// `OwnershipTransferred` event.
+///
+/// It rejects if:
+/// - Sender is not the current owner of the contract instance.
+/// - It fails to parse the parameter.
+/// - The confirmation is not `RENOUNCE_CONFIRMATION`.
+/// - Fails to log event.
+#[receive(
+    contract = "LicenseContract",
+    name = "renounceOwnership",
+    parameter = "RenounceOwnershipParams",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_renounce_ownership<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let RenounceOwnershipParams { confirmation } = ctx.parameter_cursor().get()?;
+
+    let state = host.state_mut();
+    ensure!(ctx.sender() == state.owner, ContractError::Unauthorized);
+    ensure!(
+        confirmation == RENOUNCE_CONFIRMATION,
+        CustomContractError::RenounceNotConfirmed.into()
+    );
+
+    let previous_owner = state.owner;
+    for role in ALL_ROLES {
+        if state.has_role(&previous_owner, role) {
+            state.revoke_role(&previous_owner, role);
+            logger.log(&CustomEvent::RoleRevoked(RoleEvent {
+                address: previous_owner,
+                role,
+            }))?;
+        }
+    }
+    if state.operators.contains(&previous_owner) {
+        state.remove_global_operator(&previous_owner);
+        logger.log(&CustomEvent::GlobalOperatorUpdated(GlobalOperatorUpdatedEvent {
+            operator: previous_owner,
+            update:   OperatorUpdate::Remove,
+        }))?;
+    }
+    state.owner = RENOUNCED_OWNER;
+
+    logger.log(&CustomEvent::OwnershipTransferred(OwnershipTransferredEvent {
+        previous_owner,
+        new_owner: RENOUNCED_OWNER,
+    }))?;
+    Ok(())
+}
+
+/// The parameter type for the contract function `withdraw`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct WithdrawParams {
+    /// The account to send the CCD to.
+    pub to:     AccountAddress,
+    /// The amount of CCD to send.
+    pub amount: Amount,
+}
+
+/// Send CCD held by the contract instance to an account, to recover CCD
+/// which would otherwise be stuck.
+///
+/// It rejects if:
+/// - Sender is not the owner of the contract instance.
+/// - It fails to parse the parameter.
+/// - The instance holds less than `amount`, with `InsufficientBalance`.
+/// - The receiving account does not exist.
+#[receive(
+    contract = "LicenseContract",
+    name = "withdraw",
+    parameter = "WithdrawParams",
+    error = "ContractError",
+    mutable
+)]
+fn contract_withdraw<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let WithdrawParams { to, amount } = ctx.parameter_cursor().get()?;
+
+    ensure!(ctx.sender() == host.state().owner, ContractError::Unauthorized);
+    ensure!(host.self_balance() >= amount, CustomContractError::InsufficientBalance.into());
+    host.invoke_transfer(&to, amount).map_err(CustomContractError::from)?;
+    Ok(())
+}
+
+/// Set the description and logo URL of the contract.
+///
+/// It rejects if:
+/// - Sender is not the owner of the contract instance.
+/// - It fails to parse the parameter.
+/// - The description or logo URL exceeds its maximum length.
+#[receive(
+    contract = "LicenseContract",
+    name = "setContractInfo",
+    parameter = "ContractInfo",
+    error = "ContractError",
+    mutable
+)]
+fn contract_set_contract_info<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let info: ContractInfo = ctx.parameter_cursor().get()?;
+
+    let state = host.state_mut();
+    ensure!(ctx.sender() == state.owner, ContractError::Unauthorized);
+    ensure!(
+        info.description.len() <= MAX_DESCRIPTION_LENGTH
+            && info.logo_url.len() <= MAX_LOGO_URL_LENGTH,
+        CustomContractError::ContractInfoTooLong.into()
+    );
+
+    state.contract_info = info;
+    Ok(())
+}
+
+/// Get the description and logo URL of the contract.
+#[receive(
+    contract = "LicenseContract",
+    name = "contractInfo",
+    return_value = "ContractInfo"
+)]
+fn contract_contract_info<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<ContractInfo> {
+    Ok(host.state().contract_info.clone())
+}
+
+/// Set the URL and optional hash of the collection-level metadata.
+///
+/// It rejects if:
+/// - Sender is not the owner of the contract instance.
+/// - It fails to parse the parameter.
+/// - The URL is malformed.
+#[receive(
+    contract = "LicenseContract",
+    name = "setContractMetadata",
+    parameter = "MetadataUrl",
+    error = "ContractError",
+    mutable
+)]
+fn contract_set_contract_metadata<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let metadata: MetadataUrl = ctx.parameter_cursor().get()?;
+
+    let state = host.state_mut();
+    ensure!(ctx.sender() == state.owner, ContractError::Unauthorized);
+    ensure!(check_metadata_url(&metadata.url), CustomContractError::InvalidMetadataUrl.into());
+
+    state.collection_metadata = metadata;
+    Ok(())
+}
+
+/// Get the URL and optional hash of the collection-level metadata, for
+/// marketplaces and explorers. The URL is empty if it has not been set.
+#[receive(
+    contract = "LicenseContract",
+    name = "contractMetadata",
+    return_value = "MetadataUrl"
+)]
+fn contract_contract_metadata<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<MetadataUrl> {
+    Ok(host.state().collection_metadata.clone())
+}
+
+/// Get the address of this contract instance and its owner, so clients can
+/// verify they are talking to the intended instance.
+#[receive(
+    contract = "LicenseContract",
+    name = "whoami",
+    return_value = "(ContractAddress, Address)"
+)]
+fn contract_whoami<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<(ContractAddress, Address)> {
+    Ok((ctx.self_address(), host.state().owner))
+}
+
+/// Get the owner of the contract instance.
+#[receive(
+    contract = "LicenseContract",
+    name = "getOwner",
+    return_value = "Address"
+)]
+fn contract_get_owner<S: HasStateApi>(
+    _ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ReceiveResult<Address> {
+    Ok(host.state().owner)
+}
+
+/// Set which mutating entrypoints are paused. The parameter is a bitset of the
+/// `PAUSE_*` constants, replacing the current one; `0` unpauses everything.
+///
+/// It rejects if:
+/// - Sender does not have the `Pauser` role.
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "setPausedOps",
+    parameter = "u32",
+    error = "ContractError",
+    mutable
+)]
+fn contract_set_paused_ops<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let paused_ops: u32 = ctx.parameter_cursor().get()?;
+
+    let state = host.state_mut();
+    ensure!(state.has_role(&ctx.sender(), Role::Pauser), ContractError::Unauthorized);
+    state.paused_ops = paused_ops;
+    Ok(())
+}
+
+/// The parameter type for the contract function `setPaused`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct SetPausedParams {
+    /// The token to pause or unpause.
+    pub token_id: ContractTokenId,
+    /// Whether transfers and burns of the token are paused.
+    pub paused:   bool,
+}
+
+/// Pause or unpause transfers and burns of a single token, e.g. while a
+/// dispute about the license is resolved.
+///
+/// It rejects if:
+/// - Sender does not have the `Pauser` role.
+/// - It fails to parse the parameter.
+/// - The token does not exist.
+#[receive(
+    contract = "LicenseContract",
+    name = "setPaused",
+    parameter = "SetPausedParams",
+    error = "ContractError",
+    mutable
+)]
+fn contract_set_paused<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let SetPausedParams { token_id, paused } = ctx.parameter_cursor().get()?;
+
+    let state = host.state_mut();
+    ensure!(state.has_role(&ctx.sender(), Role::Pauser), ContractError::Unauthorized);
+    ensure!(state.contains_token(&token_id), ContractError::InvalidTokenId);
+
+    if paused {
+        state.paused_tokens.insert(token_id);
+    } else {
+        state.paused_tokens.remove(&token_id);
+    }
+    Ok(())
+}
+
+/// Check whether transfers and burns of a token are paused.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "isPaused",
+    parameter = "ContractTokenId",
+    return_value = "bool",
+    error = "ContractError"
+)]
+fn contract_is_paused<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<bool> {
+    // Parse the parameter.
+    let token_id: ContractTokenId = ctx.parameter_cursor().get()?;
+    Ok(host.state().paused_tokens.contains(&token_id))
+}
+
+/// The parameter type for the contract function `pausedTokens`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct PausedTokensParams {
+    /// The number of paused tokens to skip.
+    pub skip: u32,
+    /// The maximum number of paused tokens to return.
+    pub take: u32,
+}
+
+/// Get a page of the tokens whose transfers and burns are paused, sorted by
+/// token ID.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "pausedTokens",
+    parameter = "PausedTokensParams",
+    return_value = "Vec<ContractTokenId>",
+    error = "ContractError"
+)]
+fn contract_paused_tokens<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<Vec<ContractTokenId>> {
+    // Parse the parameter.
+    let params: PausedTokensParams = ctx.parameter_cursor().get()?;
+
+    let mut paused: Vec<ContractTokenId> = host.state().paused_tokens.iter().map(|x| *x).collect();
+    paused.sort_by_key(|token_id| token_id.0);
+    Ok(paused.into_iter().skip(params.skip as usize).take(params.take as usize).collect())
+}
+
+/// The parameter type for the contract function `setFrozen`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct SetFrozenParams {
+    /// The address to freeze or unfreeze.
+    pub address: Address,
+    /// Whether the address can neither send nor receive tokens.
+    pub frozen:  bool,
+}
+
+/// Freeze or unfreeze an address, e.g. for sanctions compliance. A frozen
+/// address can neither send nor receive tokens.
+///
+/// It rejects if:
+/// - Sender does not have the `Admin` role.
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "setFrozen",
+    parameter = "SetFrozenParams",
+    error = "ContractError",
+    mutable
+)]
+fn contract_set_frozen<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
+    // Parse the parameter.
+    let SetFrozenParams { address, frozen } = ctx.parameter_cursor().get()?;
+
+    let state = host.state_mut();
+    ensure!(state.has_role(&ctx.sender(), Role::Admin), ContractError::Unauthorized);
+
+    if frozen {
+        state.frozen.insert(address);
+    } else {
+        state.frozen.remove(&address);
+    }
+    Ok(())
+}
+
+/// Check whether an address is frozen.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+#[receive(
+    contract = "LicenseContract",
+    name = "isFrozen",
+    parameter = "Address",
+    return_value = "bool",
+    error = "ContractError"
+)]
+fn contract_is_frozen<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<bool> {
+    // Parse the parameter.
+    let address: Address = ctx.parameter_cursor().get()?;
+    Ok(host.state().frozen.contains(&address))
+}
+
+/// Halt all minting, transfers and burns. Queries remain callable.
+/// Logs a `Paused` event.
+///
+/// It rejects if:
+/// - Sender does not have the `Pauser` role.
+/// - Fails to log event.
+#[receive(
+    contract = "LicenseContract",
+    name = "pause",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_pause<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<()> {
+    let state = host.state_mut();
+    ensure!(state.has_role(&ctx.sender(), Role::Pauser), ContractError::Unauthorized);
+    state.paused = true;
+
+    logger.log(&CustomEvent::Paused(PauseEvent {
+        pauser:     ctx.sender(),
+        block_time: ctx.metadata().block_time(),
+    }))?;
+    Ok(())
+}
+
+/// Resume minting, transfers and burns after a `pause`.
+/// Logs an `Unpaused` event.
+///
+/// It rejects if:
+/// - Sender does not have the `Pauser` role.
+/// - Fails to log event.
+#[receive(
+    contract = "LicenseContract",
+    name = "unpause",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_unpause<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<()> {
+    let state = host.state_mut();
+    ensure!(state.has_role(&ctx.sender(), Role::Pauser), ContractError::Unauthorized);
+    state.paused = false;
+
+    logger.log(&CustomEvent::Unpaused(PauseEvent {
+        pauser:     ctx.sender(),
+        block_time: ctx.metadata().block_time(),
+    }))?;
+    Ok(())
+}
+
+/// Grant a role to an address.
+/// Logs a `RoleGranted` event.
+///
+/// It rejects if:
+/// - Sender does not have the `Admin` role.
+/// - It fails to parse the parameter.
+/// - Fails to log event.
+#[receive(
+    contract = "LicenseContract",
+    name = "grantRole",
+    parameter = "RoleParams",
+    error = "ContractError",
+    enable_logger,
+    mutable
+)]
+fn contract_grant_role<S: HasStateApi>(
     ctx: &impl HasReceiveContext,
     host: &mut impl HasHost<State<S>, StateApiType = S>,
     logger: &mut impl HasLogger,
 ) -> ContractResult<()> {
     // Parse the parameter.
-    let TransferParams(transfers): TransferParameter = ctx.parameter_cursor().get()?;
-    // Get the sender who invoked this contract function.
-    let sender = ctx.sender();
-
-    for Transfer {
-        token_id,
-        amount,
-        from,
-        to,
-        data,
-    } in transfers
-    {
-        let (state, builder) = host.state_and_builder();
-        
-        // Authenticate the sender for this transfer
-        // ensure!(from == sender, ContractError::Unauthorized);
-
-        if from != state.owner  {
-            return Err(ContractError::Unauthorized); // Use the stored owner and operators for authorization
-        }
+    let RoleParams { address, role } = ctx.parameter_cursor().get()?;
 
-        let to_address = to.address();
-        
-        // Update the contract state
-        state.transfer(&token_id, amount, &from, &to_address, builder)?;
-
-        // Log transfer event
-        logger.log(&Cis2Event::Transfer(TransferEvent {
-            token_id,
-            amount,
-            from,
-            to: to_address,
-        }))?;
+    let (state, builder) = host.state_and_builder();
+    ensure!(state.has_role(&ctx.sender(), Role::Admin), ContractError::Unauthorized);
+    state.grant_role(&address, role, builder);
 
-        // If the receiver is a contract: invoke the receive hook function.
-        if let Receiver::Contract(address, function) = to {
-            let parameter = OnReceivingCis2Params {
-                token_id,
-                amount,
-                from,
-                data,
-            };
-            host.invoke_contract(
-                &address,
-                &parameter,
-                function.as_entrypoint_name(),
-                Amount::zero(),
-            )?;
-        }
-    }
+    logger.log(&CustomEvent::RoleGranted(RoleEvent {
+        address,
+        role,
+    }))?;
     Ok(())
 }
 
-/// Enable or disable addresses as operators of the sender address.
-/// Logs an `UpdateOperator` event.
+/// Revoke a role from an address.
+/// Logs a `RoleRevoked` event.
 ///
 /// It rejects if:
+/// - Sender does not have the `Admin` role.
 /// - It fails to parse the parameter.
 /// - Fails to log event.
 #[receive(
     contract = "LicenseContract",
-    name = "updateOperator",
-    parameter = "UpdateOperatorParams",
+    name = "revokeRole",
+    parameter = "RoleParams",
     error = "ContractError",
     enable_logger,
     mutable
 )]
-fn contract_update_operator<S: HasStateApi>(
+fn contract_revoke_role<S: HasStateApi>(
     ctx: &impl HasReceiveContext,
     host: &mut impl HasHost<State<S>, StateApiType = S>,
     logger: &mut impl HasLogger,
 ) -> ContractResult<()> {
     // Parse the parameter.
-    let UpdateOperatorParams(params) = ctx.parameter_cursor().get()?;
-    // Get the sender who invoked this contract function.
-    let sender = ctx.sender();
-    let (state, builder) = host.state_and_builder();
-    for param in params {
-        // Update the operator in the state.
-        match param.update {
-            OperatorUpdate::Add => state.add_operator(&sender, &param.operator, builder),
-            OperatorUpdate::Remove => state.remove_operator(&sender, &param.operator),
-        }
+    let RoleParams { address, role } = ctx.parameter_cursor().get()?;
 
-        // Log the appropriate event
-        logger.log(
-            &Cis2Event::<ContractTokenId, ContractTokenAmount>::UpdateOperator(
-                UpdateOperatorEvent {
-                    owner: sender,
-                    operator: param.operator,
-                    update: param.update,
-                },
-            ),
-        )?;
-    }
+    let state = host.state_mut();
+    ensure!(state.has_role(&ctx.sender(), Role::Admin), ContractError::Unauthorized);
+    state.revoke_role(&address, role);
 
+    logger.log(&CustomEvent::RoleRevoked(RoleEvent {
+        address,
+        role,
+    }))?;
     Ok(())
 }
 
-/// Takes a list of queries. Each query is an owner address and some address to
-/// check as an operator of the owner address.
+/// The parameter type for the contract function `purgeOperator`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct PurgeOperatorParams {
+    /// The operator to remove everywhere.
+    pub operator: Address,
+    /// The address to resume the purge at, as returned by a previous call.
+    pub cursor:   Option<Address>,
+}
+
+/// Remove an operator everywhere: from the global minting operators, from the
+/// operators of every address and from the scoped approvals of every address.
+/// Logs a `GlobalOperatorUpdated` event if the operator was a global operator,
+/// an `UpdateOperator` event for each address the operator is removed from and
+/// a `ScopedOperatorUpdated` event for each scoped approval removed.
+///
+/// Since one call can only log a limited number of events, the address
+/// states are scanned from `cursor` and the scan stops once the log budget is
+/// used up. The returned cursor, if any, is the address to continue the purge
+/// at in a following call. Addresses added in between do not shift it.
 ///
 /// It rejects if:
+/// - Sender is not the owner of the contract instance.
 /// - It fails to parse the parameter.
+/// - Fails to log event.
 #[receive(
     contract = "LicenseContract",
-    name = "operatorOf",
-    parameter = "OperatorOfQueryParams",
-    return_value = "OperatorOfQueryResponse",
-    error = "ContractError"
+    name = "purgeOperator",
+    parameter = "PurgeOperatorParams",
+    return_value = "Option<Address>",
+    error = "ContractError",
+    enable_logger,
+    mutable
 )]
-fn contract_operator_of<S: HasStateApi>(
+fn contract_purge_operator<S: HasStateApi>(
     ctx: &impl HasReceiveContext,
-    host: &impl HasHost<State<S>, StateApiType = S>,
-) -> ContractResult<OperatorOfQueryResponse> {
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> ContractResult<Option<Address>> {
     // Parse the parameter.
-    let params: OperatorOfQueryParams = ctx.parameter_cursor().get()?;
-    // Build the response.
-    let mut response = Vec::with_capacity(params.queries.len());
-    for query in params.queries {
-        // Query the state for address being an operator of owner.
-        let is_operator = host.state().is_operator(&query.address, &query.owner);
-        response.push(is_operator);
+    let PurgeOperatorParams { operator, cursor } = ctx.parameter_cursor().get()?;
+
+    let state = host.state_mut();
+    ensure!(ctx.sender() == state.owner, ContractError::Unauthorized);
+
+    let mut budget = MAX_LOG_EVENTS;
+    if state.operators.contains(&operator) {
+        state.remove_global_operator(&operator);
+        logger.log(&CustomEvent::GlobalOperatorUpdated(GlobalOperatorUpdatedEvent {
+            operator,
+            update: OperatorUpdate::Remove,
+        }))?;
+        budget -= 1;
     }
-    let result = OperatorOfQueryResponse::from(response);
-    Ok(result)
-}
 
-/// Parameter type for the CIS-2 function `balanceOf` specialized to the subset
-/// of TokenIDs used by this contract.
-type ContractBalanceOfQueryParams = BalanceOfQueryParams<ContractTokenId>;
-/// Response type for the CIS-2 function `balanceOf` specialized to the subset
-/// of TokenAmounts used by this contract.
-type ContractBalanceOfQueryResponse = BalanceOfQueryResponse<ContractTokenAmount>;
+    // The address states are iterated in the order of their serialized keys,
+    // so the scan resumes at the cursor.
+    let cursor_key = cursor.map(|cursor| to_bytes(&cursor));
+    // Collect the removals first, the state cannot be modified while iterating.
+    let mut owners = Vec::new();
+    let mut scoped = Vec::new();
+    let mut next_cursor = None;
+    for (owner, address_state) in state.state.iter() {
+        if cursor_key.as_ref().is_some_and(|key| to_bytes(&*owner) < *key) {
+            continue;
+        }
+        let removes_operator = address_state.operators.contains(&operator);
+        let removals: Vec<ContractTokenId> = address_state
+            .owned_tokens
+            .iter()
+            .filter(|token_id| state.is_scoped_operator(&operator, &owner, token_id))
+            .map(|token_id| *token_id)
+            .collect();
+        let events = usize::from(removes_operator) + removals.len();
+        if events > budget {
+            // Remove what still fits and resume at this owner, so an owner
+            // with more removals than one call can log is purged over several
+            // calls.
+            if removes_operator && budget > 0 {
+                owners.push(*owner);
+                budget -= 1;
+            }
+            scoped.extend(removals.into_iter().take(budget).map(|token_id| (*owner, token_id)));
+            next_cursor = Some(*owner);
+            break;
+        }
+        if removes_operator {
+            owners.push(*owner);
+        }
+        scoped.extend(removals.into_iter().map(|token_id| (*owner, token_id)));
+        budget -= events;
+    }
 
-/// Get the balance of given token IDs and addresses.
+    for owner in owners {
+        state.remove_operator(&owner, &operator);
+        logger.log(&Cis2Event::<ContractTokenId, ContractTokenAmount>::UpdateOperator(
+            UpdateOperatorEvent {
+                owner,
+                operator,
+                update: OperatorUpdate::Remove,
+            },
+        ))?;
+    }
+    for (owner, token_id) in scoped {
+        state.scoped_operators.entry((owner, token_id)).and_modify(|operators| {
+            operators.remove(&operator);
+        });
+        logger.log(&CustomEvent::ScopedOperatorUpdated(ScopedOperatorUpdatedEvent {
+            owner,
+            operator,
+            token_id,
+            update: OperatorUpdate::Remove,
+        }))?;
+    }
+    Ok(next_cursor)
+}
+
+/// Set how long after minting a token can only be transferred by the owner of
+/// the contract or an `Admin`. Applies to already minted tokens as well.
 ///
 /// It rejects if:
+/// - Sender is not the owner of the contract instance.
 /// - It fails to parse the parameter.
-/// - Any of the queried `token_id` does not exist.
 #[receive(
     contract = "LicenseContract",
-    name = "balanceOf",
-    parameter = "ContractBalanceOfQueryParams",
-    return_value = "ContractBalanceOfQueryResponse",
-    error = "ContractError"
+    name = "setLockup",
+    parameter = "Duration",
+    error = "ContractError",
+    mutable
 )]
-fn contract_balance_of<S: HasStateApi>(
+fn contract_set_lockup<S: HasStateApi>(
     ctx: &impl HasReceiveContext,
-    host: &impl HasHost<State<S>, StateApiType = S>,
-) -> ContractResult<ContractBalanceOfQueryResponse> {
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
     // Parse the parameter.
-    let params: ContractBalanceOfQueryParams = ctx.parameter_cursor().get()?;
-    // Build the response.
-    let mut response = Vec::with_capacity(params.queries.len());
-    for query in params.queries {
-        // Query the state for balance.
-        let amount = host.state().balance(&query.token_id, &query.address)?;
-        response.push(amount);
-    }
-    let result = ContractBalanceOfQueryResponse::from(response);
-    Ok(result)
+    let lockup: Duration = ctx.parameter_cursor().get()?;
+
+    let state = host.state_mut();
+    ensure!(ctx.sender() == state.owner, ContractError::Unauthorized);
+    state.lockup = lockup;
+    Ok(())
 }
 
-/// Parameter type for the CIS-2 function `tokenMetadata` specialized to the
-/// subset of TokenIDs used by this contract.
-type ContractTokenMetadataQueryParams = TokenMetadataQueryParams<ContractTokenId>;
+/// The parameter type for the contract function `reserveTokenRange`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct ReserveTokenRangeParams {
+    /// The minter to restrict.
+    pub minter: Address,
+    /// The inclusive range of token IDs the minter can mint, `None` to lift
+    /// the restriction.
+    pub range:  Option<(u32, u32)>,
+}
 
-/// Get the token metadata URLs and checksums given a list of token IDs.
+/// Restrict a minter to a range of token IDs disjoint from the ranges of the
+/// other minters, so concurrent minters never collide. Minters without a range
+/// can mint any token ID.
 ///
 /// It rejects if:
+/// - Sender does not have the `Admin` role.
 /// - It fails to parse the parameter.
-/// - Any of the queried `token_id` does not exist.
+/// - The range is empty or overlaps the range of another minter.
 #[receive(
     contract = "LicenseContract",
-    name = "tokenMetadata",
-    parameter = "ContractTokenMetadataQueryParams",
-    return_value = "TokenMetadataQueryResponse",
-    error = "ContractError"
+    name = "reserveTokenRange",
+    parameter = "ReserveTokenRangeParams",
+    error = "ContractError",
+    mutable
 )]
-fn contract_token_metadata<S: HasStateApi>(
+fn contract_reserve_token_range<S: HasStateApi>(
     ctx: &impl HasReceiveContext,
-    host: &impl HasHost<State<S>, StateApiType = S>,
-) -> ContractResult<TokenMetadataQueryResponse> {
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
     // Parse the parameter.
-    let params: ContractTokenMetadataQueryParams = ctx.parameter_cursor().get()?;
-    // Build the response.
-    let mut response = Vec::with_capacity(params.queries.len());
-    for token_id in params.queries {
-        // Check the token exists.
-        ensure!(
-            host.state().contains_token(&token_id),
-            ContractError::InvalidTokenId
-        );
+    let ReserveTokenRangeParams { minter, range } = ctx.parameter_cursor().get()?;
 
-        let metadata_url: MetadataUrl = host
-            .state()
-            .metadata
-            .get(&token_id)
-            .map(|metadata| MetadataUrl {
-                hash: None,
-                url: metadata.url.to_owned(),
-            })
-            .ok_or(ContractError::InvalidTokenId)?;
-        response.push(metadata_url);
+    let state = host.state_mut();
+    ensure!(state.has_role(&ctx.sender(), Role::Admin), ContractError::Unauthorized);
+    match range {
+        Some((start, end)) => state.reserve_range(&minter, start, end)?,
+        None => {
+            state.reserved_ranges.remove(&minter);
+        }
     }
-    let result = TokenMetadataQueryResponse::from(response);
-    Ok(result)
+    Ok(())
 }
 
-/// Get the supported standards or addresses for a implementation given list of
-/// standard identifiers.
+/// Set the base URL of the token metadata, e.g. when migrating the metadata
+/// backend. Only tokens minted afterwards use the new base URL. Surrounding
+/// whitespace is trimmed.
 ///
 /// It rejects if:
+/// - Sender is not the owner of the contract instance.
 /// - It fails to parse the parameter.
 #[receive(
     contract = "LicenseContract",
-    name = "supports",
-    parameter = "SupportsQueryParams",
-    return_value = "SupportsQueryResponse",
-    error = "ContractError"
+    name = "updateMetadataBaseUrl",
+    parameter = "String",
+    error = "ContractError",
+    mutable
 )]
-fn contract_supports<S: HasStateApi>(
+fn contract_update_metadata_base_url<S: HasStateApi>(
     ctx: &impl HasReceiveContext,
-    host: &impl HasHost<State<S>, StateApiType = S>,
-) -> ContractResult<SupportsQueryResponse> {
+    host: &mut impl HasHost<State<S>, StateApiType = S>,
+) -> ContractResult<()> {
     // Parse the parameter.
-    let params: SupportsQueryParams = ctx.parameter_cursor().get()?;
+    let base_url: String = ctx.parameter_cursor().get()?;
 
-    // Build the response.
-    let mut response = Vec::with_capacity(params.queries.len());
-    for std_id in params.queries {
-        if SUPPORTS_STANDARDS.contains(&std_id.as_standard_identifier()) {
-            response.push(SupportResult::Support);
-        } else {
-            response.push(host.state().have_implementors(&std_id));
-        }
-    }
-    let result = SupportsQueryResponse::from(response);
-    Ok(result)
+    let state = host.state_mut();
+    ensure!(ctx.sender() == state.owner, ContractError::Unauthorized);
+    state.metadata_base_url = base_url.trim().to_string();
+    Ok(())
 }
 
-/// Set the addresses for an implementation given a standard identifier and a
-/// list of contract addresses.
+/// Register an address, allowing tokens to be minted to it when registration
+/// is required. Succeeds even if the address is already registered.
 ///
 /// It rejects if:
 /// - Sender is not the owner of the contract instance.
 /// - It fails to parse the parameter.
 #[receive(
     contract = "LicenseContract",
-    name = "setImplementors",
-    parameter = "SetImplementorsParams",
+    name = "registerAddress",
+    parameter = "Address",
     error = "ContractError",
     mutable
 )]
-fn contract_set_implementor<S: HasStateApi>(
+fn contract_register_address<S: HasStateApi>(
     ctx: &impl HasReceiveContext,
     host: &mut impl HasHost<State<S>, StateApiType = S>,
 ) -> ContractResult<()> {
-    // Authorize the sender.
-    // ensure!(
-    //     ctx.sender().matches_account(&ctx.owner()),
-    //     ContractError::Unauthorized
-    // );
-    let sender = ctx.sender();
-
-    if ctx.sender().matches_account(&ctx.owner()) {
-        return Err(ContractError::Unauthorized); // Use the stored owner and operators for authorization
-    }
     // Parse the parameter.
-    let params: SetImplementorsParams = ctx.parameter_cursor().get()?;
-    // Update the implementors in the state
-    host.state_mut()
-        .set_implementors(params.id, params.implementors);
+    let address: Address = ctx.parameter_cursor().get()?;
+
+    let (state, builder) = host.state_and_builder();
+    ensure!(ctx.sender() == state.owner, ContractError::Unauthorized);
+    state.register_address(&address, builder);
     Ok(())
 }
 
-/// The parameter type for the contract function `upgrade`.
-/// Takes the new module and optionally a migration function to call in the new
-/// module after the upgrade.
-#[derive(Serialize, SchemaType)]
-struct UpgradeParams {
-    /// The new module reference.
-    module:  ModuleReference,
-    /// Optional entrypoint to call in the new module after upgrade.
-    migrate: Option<(OwnedEntrypointName, OwnedParameter)>,
-}
+// Unit tests for states which cannot be reached through the entrypoints, so
+// they use the test infrastructure of `concordium_std` rather than the
+// integration tests against the built module.
+#[cfg(test)]
+#[allow(deprecated)]
+mod tests {
+    use super::*;
+    use test_infrastructure::*;
 
-#[receive(
-    contract = "LicenseContract",
-    name = "upgrade",
-    parameter = "UpgradeParams",
-    low_level
-)]
-fn contract_upgrade(
-    ctx: &ReceiveContext,
-    host: &mut LowLevelHost,
-) -> ReceiveResult<()> {
-    // Check that only the owner is authorized to upgrade the smart contract.
-    // ensure!(ctx.sender().matches_account(&ctx.owner()));
-    let sender = ctx.sender();
+    const OWNER: Address = Address::Account(AccountAddress([0u8; 32]));
+    const HOLDER: Address = Address::Account(AccountAddress([1u8; 32]));
 
-    if !sender.matches_account(&ctx.owner()) {
-        // Optionally log a message or handle unauthorized access
-        return Ok(()); // Exit the function without performing the upgrade
-    }
-    // Parse the parameter.
-    let params: UpgradeParams = ctx.parameter_cursor().get()?;
-    // Trigger the upgrade.
-    host.upgrade(params.module)?;
-    // Call the migration function if provided.
-    if let Some((func, parameters)) = params.migrate {
-        host.invoke_contract_raw(
-            &ctx.self_address(),
-            parameters.as_parameter(),
-            func.as_entrypoint_name(),
-            Amount::zero(),
-        )?;
-    }
-    Ok(())
-}
+    /// Test that the `mintNext` counter rejects instead of wrapping once the
+    /// token IDs are exhausted.
+    #[test]
+    fn test_next_token_id_exhausted() {
+        let mut state_builder = TestStateBuilder::new();
+        let mut state = State::empty(&mut state_builder, OWNER);
+        state.next_id = u32::MAX - 1;
 
-// Function to update the owner
-fn update_owner<S: HasStateApi>(
-    ctx: &impl HasReceiveContext,
-    state: &mut State<S>,
-    new_owner_address: &str,
-) -> Result<(), CustomContractError> {
-    // Check if the caller is the current owner
-    let caller = ctx.sender();
-    if caller != state.owner {
-        return Err(CustomContractError::Unauthorized);
+        assert_eq!(state.next_token_id(), Ok(TokenIdU32(u32::MAX - 1)));
+        assert_eq!(
+            state.next_token_id(),
+            Err(ContractError::Custom(CustomContractError::TokenIdSpaceExhausted))
+        );
     }
 
-    let new_owner_address = "4MwARWeXdMs3YZ5MPPn2561ceani6AJAVTNPtwS6tceaG2qatK";
-    // Decode the new owner address from Base58
-    let new_owner_bytes = bs58::decode(new_owner_address)
-        .into_vec()
-        .map_err(|_| CustomContractError::ParseParams)?; // Handle parsing errors
-
-    // Ensure the byte array is exactly 32 bytes
-    let new_owner = AccountAddress(new_owner_bytes.try_into().map_err(|_| CustomContractError::ParseParams)?);
+    /// Test that `tokenMetadata` falls back to the URL built from the base URL
+    /// for an existing token without stored metadata.
+    #[test]
+    fn test_token_metadata_fallback() {
+        let mut state_builder = TestStateBuilder::new();
+        let mut state = State::empty(&mut state_builder, OWNER);
+        state.metadata_base_url = "https://licenses.example.com/".to_string();
+        let now = Timestamp::from_timestamp_millis(0);
+        let metadata = TokenMetadata {
+            url: "https://elsewhere.example.com/1.json".to_string(),
+            hash: Some([1u8; 32]),
+            content_type: None,
+            expiry: None,
+            additional_urls: Vec::new(),
+            issued_at: now,
+            tier: 0,
+        };
+        state.mint(TokenIdU32(1), metadata, &HOLDER, now, &mut state_builder).expect("Mint");
+        state.remove_metadata(&TokenIdU32(1));
+        let fallback = state.token_metadata_url(&TokenIdU32(1));
+        let host = TestHost::new(state, state_builder);
 
-    // Update the owner in the state
-    state.owner = Address::Account(new_owner);
+        let parameter = to_bytes(&ContractTokenMetadataQueryParams {
+            queries: vec![TokenIdU32(1)],
+        });
+        let mut ctx = TestReceiveContext::empty();
+        ctx.set_parameter(&parameter);
+        let response = contract_token_metadata(&ctx, &host).expect("Query succeeds");
+        assert_eq!(response.0, [MetadataUrl {
+            url:  fallback,
+            hash: None,
+        }]);
 
-    Ok(())
-}
\ No newline at end of file
+        let parameter = to_bytes(&ContractTokenMetadataQueryParams {
+            queries: vec![TokenIdU32(2)],
+        });
+        ctx.set_parameter(&parameter);
+        let rejected = contract_token_metadata(&ctx, &host).expect_err("Unknown token");
+        assert_eq!(rejected, ContractError::InvalidTokenId);
+    }
+}
@@ -1,5 +1,11 @@
+use concordium_cis2::*;
 use concordium_smart_contract_testing::*;
+use concordium_std::{AccountSignatures, Deserial, HashSha2256, Serial};
+// The CIS-2 transfer, rather than the transfer of the testing library.
+use concordium_cis2::Transfer;
 use concordium::*;
+// The contract events, rather than the events of the testing library.
+use concordium::ContractEvent;
 
 /// A test account.
 const ALICE: AccountAddress = AccountAddress([0u8; 32]);
@@ -11,80 +17,3660 @@ const ACC_INITIAL_BALANCE: Amount = Amount::from_ccd(10_000);
 /// A [`Signer`] with one set of keys, used for signing transactions.
 const SIGNER: Signer = Signer::with_one_key();
 
-/// Test that invoking the `receive` endpoint with the `false` parameter
-/// succeeds in updating the contract.
-#[test]
-fn test_throw_no_error() {
-    let (mut chain, init) = initialize();
+/// A second test account.
+const BOB: AccountAddress = AccountAddress([1u8; 32]);
+const BOB_ADDR: Address = Address::Account(BOB);
 
-    // Update the contract via the `receive` entrypoint with the parameter `false`.
-    chain
-        .contract_update(SIGNER, ALICE, ALICE_ADDR, Energy::from(10_000), UpdateContractPayload {
-            address:      init.contract_address,
-            amount:       Amount::zero(),
-            receive_name: OwnedReceiveName::new_unchecked("concordium.receive".to_string()),
-            message:      OwnedParameter::from_serial(&false)
-                .expect("Parameter within size bounds"),
-        })
-        .expect("Update succeeds with `false` as input.");
-}
+/// A third test account.
+const CHARLIE: AccountAddress = AccountAddress([2u8; 32]);
+const CHARLIE_ADDR: Address = Address::Account(CHARLIE);
 
-/// Test that invoking the `receive` endpoint with the `true` parameter
-/// results in the `CustomError` being thrown.
+/// Test that `holderCount` counts every address holding at least one token.
 #[test]
-fn test_throw_error() {
-    let (mut chain, init) = initialize();
+fn test_holder_count() {
+    let (mut chain, contract) = initialize_license();
 
-    // Update the contract via the `receive` entrypoint with the parameter `true`.
-    let update = chain
-        .contract_update(SIGNER, ALICE, ALICE_ADDR, Energy::from(10_000), UpdateContractPayload {
-            address:      init.contract_address,
-            amount:       Amount::zero(),
-            receive_name: OwnedReceiveName::new_unchecked("concordium.receive".to_string()),
-            message:      OwnedParameter::from_serial(&true).expect("Parameter within size bounds"),
-        })
-        .expect_err("Update fails with `true` as input.");
+    mint(&mut chain, contract, ALICE, 1).expect("Mint token 1");
+    mint(&mut chain, contract, ALICE, 2).expect("Mint token 2");
+    mint(&mut chain, contract, BOB, 3).expect("Mint token 3");
 
-    // Check that the contract returned `CustomError`.
-    let error: Error = update.parse_return_value().expect("Deserialize `Error`");
-    assert_eq!(error, Error::CustomError);
+    let count: u64 = view(&chain, contract, "holderCount", &());
+    assert_eq!(count, 2);
 }
 
-/// Helper method for initializing the contract.
+/// Helper method for initializing the `LicenseContract`.
 ///
 /// Does the following:
 ///  - Creates the [`Chain`]
-///  - Creates one account, `Alice` with `10_000` CCD as the initial balance.
-///  - Initializes the contract.
-///  - Returns the [`Chain`] and the [`ContractInitSuccess`]
-fn initialize() -> (Chain, ContractInitSuccess) {
-    // Initialize the test chain.
+///  - Creates the `Alice`, `Bob` and `Charlie` accounts with `10_000` CCD.
+///  - Deploys the module and initializes the contract with `Alice` as owner.
+///  - Returns the [`Chain`] and the address of the contract instance.
+fn initialize_license() -> (Chain, ContractAddress) {
+    initialize_license_with(InitParams::default())
+}
+
+/// Helper method for initializing the `LicenseContract` with the given
+/// parameters, see [`initialize_license`].
+fn initialize_license_with(params: InitParams) -> (Chain, ContractAddress) {
     let mut chain = Chain::new();
 
-    // Create the test account.
     chain.create_account(Account::new(ALICE, ACC_INITIAL_BALANCE));
+    chain.create_account(Account::new(BOB, ACC_INITIAL_BALANCE));
+    chain.create_account(Account::new(CHARLIE, ACC_INITIAL_BALANCE));
 
-    // Load the module.
     let module = module_load_v1("./concordium-out/module.wasm.v1").expect("Module exists at path");
-    // Deploy the module.
     let deployment = chain.module_deploy_v1(SIGNER, ALICE, module).expect("Deploy valid module");
 
-    let parameter = CustomInputParameter { num: 0 };
-
-    // Initialize the contract.
     let init = chain
-        .contract_init(
-            SIGNER,
-            ALICE,
-            Energy::from(10_000),
-            InitContractPayload {
-                amount: Amount::zero(),
-                mod_ref: deployment.module_reference,
-                init_name: OwnedContractName::new_unchecked("init_concordium".to_string()),
-                param: OwnedParameter::from_serial(&parameter).expect("Parameter is valid."),
-            },
-        )
+        .contract_init(SIGNER, ALICE, Energy::from(10_000), InitContractPayload {
+            amount:    Amount::zero(),
+            mod_ref:   deployment.module_reference,
+            init_name: OwnedContractName::new_unchecked("init_LicenseContract".to_string()),
+            param:     OwnedParameter::from_serial(&params).expect("Parameter is valid."),
+        })
         .expect("Initializing contract");
 
-    (chain, init)
+    (chain, init.contract_address)
+}
+
+/// Helper method for invoking a mutable entrypoint of the `LicenseContract`.
+fn update<P: Serial>(
+    chain: &mut Chain,
+    contract: ContractAddress,
+    sender: AccountAddress,
+    entrypoint: &str,
+    parameter: &P,
+) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+    chain.contract_update(
+        SIGNER,
+        sender,
+        Address::Account(sender),
+        Energy::from(100_000),
+        UpdateContractPayload {
+            address:      contract,
+            amount:       Amount::zero(),
+            receive_name: receive_name(entrypoint),
+            message:      OwnedParameter::from_serial(parameter)
+                .expect("Parameter within size bounds"),
+        },
+    )
+}
+
+/// Helper method for invoking a view entrypoint and parsing its return value.
+fn view<P: Serial, R: Deserial>(
+    chain: &Chain,
+    contract: ContractAddress,
+    entrypoint: &str,
+    parameter: &P,
+) -> R {
+    chain
+        .contract_invoke(ALICE, ALICE_ADDR, Energy::from(100_000), UpdateContractPayload {
+            address:      contract,
+            amount:       Amount::zero(),
+            receive_name: receive_name(entrypoint),
+            message:      OwnedParameter::from_serial(parameter)
+                .expect("Parameter within size bounds"),
+        })
+        .expect("View succeeds")
+        .parse_return_value()
+        .expect("Deserialize return value")
+}
+
+/// Helper method for building the receive name of a `LicenseContract`
+/// entrypoint.
+fn receive_name(entrypoint: &str) -> OwnedReceiveName {
+    OwnedReceiveName::new_unchecked(format!("LicenseContract.{}", entrypoint))
+}
+
+/// Helper method for minting a token to `owner`, sent by the contract owner.
+fn mint(
+    chain: &mut Chain,
+    contract: ContractAddress,
+    owner: AccountAddress,
+    token: u32,
+) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+    mint_as(chain, contract, ALICE, owner, token)
+}
+
+/// Test that the owner can hand over the contract and that the new owner
+/// takes over the owner privileges.
+#[test]
+fn test_transfer_ownership() {
+    let (mut chain, contract) = initialize_license();
+
+    let transferred =
+        update(&mut chain, contract, ALICE, "transferOwnership", &TransferOwnershipParams {
+            new_owner:            BOB_ADDR,
+            allow_contract_owner: false,
+        })
+        .expect("Owner transfers ownership");
+
+    let events: Vec<CustomEvent> = transferred
+        .events()
+        .flat_map(|(_, events)| events.iter().map(|e| e.parse().expect("Deserialize event")))
+        .collect();
+    let mut expected: Vec<CustomEvent> = [Role::Minter, Role::Burner, Role::Pauser, Role::Admin]
+        .into_iter()
+        .flat_map(|role| {
+            [
+                CustomEvent::RoleRevoked(RoleEvent {
+                    address: ALICE_ADDR,
+                    role,
+                }),
+                CustomEvent::RoleGranted(RoleEvent {
+                    address: BOB_ADDR,
+                    role,
+                }),
+            ]
+        })
+        .collect();
+    expected.push(CustomEvent::OwnershipTransferred(OwnershipTransferredEvent {
+        previous_owner: ALICE_ADDR,
+        new_owner:      BOB_ADDR,
+    }));
+    assert_eq!(events, expected);
+
+    // Only the new owner can use owner-only entrypoints.
+    let info = ContractInfo::default();
+    update(&mut chain, contract, ALICE, "setContractInfo", &info)
+        .expect_err("Previous owner is no longer the owner");
+    update(&mut chain, contract, BOB, "setContractInfo", &info).expect("New owner is the owner");
+}
+
+/// Test that the roles of the owner move to the new owner with the ownership.
+#[test]
+fn test_transfer_ownership_moves_roles() {
+    let (mut chain, contract) = initialize_license();
+    update(&mut chain, contract, ALICE, "transferOwnership", &TransferOwnershipParams {
+        new_owner:            BOB_ADDR,
+        allow_contract_owner: false,
+    })
+    .expect("Owner transfers ownership");
+
+    let minter = RoleParams {
+        address: CHARLIE_ADDR,
+        role:    Role::Minter,
+    };
+    let rejected = update(&mut chain, contract, ALICE, "grantRole", &minter)
+        .expect_err("Previous owner is no longer an admin");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Unauthorized);
+    mint_as(&mut chain, contract, ALICE, ALICE, 1).expect_err("Previous owner is no minter");
+
+    update(&mut chain, contract, BOB, "grantRole", &minter).expect("New owner is an admin");
+    mint_as(&mut chain, contract, BOB, BOB, 1).expect("New owner is a minter");
+    update(&mut chain, contract, BOB, "pause", &()).expect("New owner is a pauser");
+}
+
+/// Test that only the current owner can transfer the ownership.
+#[test]
+fn test_transfer_ownership_unauthorized() {
+    let (mut chain, contract) = initialize_license();
+
+    let update = update(&mut chain, contract, BOB, "transferOwnership", &TransferOwnershipParams {
+        new_owner:            BOB_ADDR,
+        allow_contract_owner: false,
+    })
+    .expect_err("Non-owner cannot transfer ownership");
+
+    let error: ContractError = update.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Unauthorized);
+}
+
+/// Test that the Base58 convenience constructor decodes an account address.
+#[test]
+fn test_transfer_ownership_params_from_account_str() {
+    let params = TransferOwnershipParams::from_account_str(
+        "4MwARWeXdMs3YZ5MPPn2561ceani6AJAVTNPtwS6tceaG2qatK",
+    )
+    .expect("Valid account address");
+    assert!(matches!(params.new_owner, Address::Account(_)));
+
+    TransferOwnershipParams::from_account_str("not-base58!").expect_err("Invalid address");
+}
+
+/// Helper method for minting a token to `owner`, sent by `sender`.
+fn mint_as(
+    chain: &mut Chain,
+    contract: ContractAddress,
+    sender: AccountAddress,
+    owner: AccountAddress,
+    token: u32,
+) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+    update(chain, contract, sender, "mint", &MintParams {
+        owner,
+        token: TokenIdU32(token),
+        web3id: "@license".to_string(),
+        hash: None,
+        content_type: None,
+        expiry: None,
+        tier: 0,
+    })
+}
+
+/// Test that the owner can set the contract info and read it back.
+#[test]
+fn test_contract_info() {
+    let (mut chain, contract) = initialize_license();
+
+    let info = ContractInfo {
+        description: "AesirX licenses".to_string(),
+        logo_url:    "https://aesirx.io/logo.png".to_string(),
+    };
+    update(&mut chain, contract, ALICE, "setContractInfo", &info)
+        .expect("Owner sets contract info");
+
+    let stored: ContractInfo = view(&chain, contract, "contractInfo", &());
+    assert_eq!(stored, info);
+
+    // Non-owners and overlong descriptions are rejected.
+    update(&mut chain, contract, BOB, "setContractInfo", &info).expect_err("Non-owner rejected");
+    let update = update(&mut chain, contract, ALICE, "setContractInfo", &ContractInfo {
+        description: "x".repeat(257),
+        logo_url:    String::new(),
+    })
+    .expect_err("Overlong description rejected");
+    let error: ContractError = update.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::ContractInfoTooLong));
+}
+
+/// Test that only the owner can set the implementors of a standard.
+#[test]
+fn test_set_implementors_authorization() {
+    let (mut chain, contract) = initialize_license();
+
+    let params = SetImplementorsParams {
+        id:           StandardIdentifierOwned::new_unchecked("CIS-42".to_string()),
+        implementors: vec![ContractAddress::new(42, 0)],
+    };
+
+    update(&mut chain, contract, ALICE, "setImplementors", &params)
+        .expect("Owner sets implementors");
+
+    let rejected = update(&mut chain, contract, BOB, "setImplementors", &params)
+        .expect_err("Non-owner cannot set implementors");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::Unauthorized));
+}
+
+/// Test that a queued upgrade can only be executed once the timelock delay
+/// has passed.
+#[test]
+fn test_timelocked_upgrade() {
+    let (mut chain, contract) = initialize_license();
+
+    let operation = TimelockOperation::Upgrade(UpgradeParams {
+        module:  module_reference(),
+        migrate: None,
+    });
+    update(&mut chain, contract, ALICE, "queueOperation", &operation).expect("Queue upgrade");
+
+    let rejected = update(&mut chain, contract, ALICE, "executeOperation", &operation)
+        .expect_err("Upgrade before the delay fails");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::TimelockNotElapsed));
+
+    chain.tick_block_time(Duration::from_days(2)).expect("Block time does not overflow");
+    update(&mut chain, contract, ALICE, "executeOperation", &operation)
+        .expect("Upgrade after the delay succeeds");
+
+    // The operation is consumed by the execution.
+    update(&mut chain, contract, ALICE, "executeOperation", &operation)
+        .expect_err("Operation cannot be executed twice");
+}
+
+/// Helper method for getting the reference of the contract module.
+fn module_reference() -> ModuleReference {
+    module_load_v1("./concordium-out/module.wasm.v1")
+        .expect("Module exists at path")
+        .get_module_ref()
+}
+
+/// Test that the holder of a token can transfer it.
+#[test]
+fn test_transfer_by_holder() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    transfer(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 1).expect("Holder transfers");
+
+    assert_eq!(balance_of(&chain, contract, CHARLIE_ADDR, 1), TokenAmountU64(1));
+    assert_eq!(balance_of(&chain, contract, BOB_ADDR, 1), TokenAmountU64(0));
+}
+
+/// Test that an operator of the holder can transfer the holder's token.
+#[test]
+fn test_transfer_by_operator() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    add_operator(&mut chain, contract, BOB, CHARLIE_ADDR).expect("Add operator");
+
+    transfer(&mut chain, contract, CHARLIE, BOB_ADDR, ALICE_ADDR, 1).expect("Operator transfers");
+
+    assert_eq!(balance_of(&chain, contract, ALICE_ADDR, 1), TokenAmountU64(1));
+}
+
+/// Test that neither the contract owner nor any other address can transfer a
+/// token they neither hold nor operate.
+#[test]
+fn test_transfer_unauthorized() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    for sender in [ALICE, CHARLIE] {
+        let rejected = transfer(&mut chain, contract, sender, BOB_ADDR, CHARLIE_ADDR, 1)
+            .expect_err("Unauthorized transfer");
+        let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+        assert_eq!(error, ContractError::Unauthorized);
+    }
+    assert_eq!(balance_of(&chain, contract, BOB_ADDR, 1), TokenAmountU64(1));
+}
+
+/// Helper method for transferring a token between two addresses.
+fn transfer(
+    chain: &mut Chain,
+    contract: ContractAddress,
+    sender: AccountAddress,
+    from: Address,
+    to: Address,
+    token: u32,
+) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+    transfer_amount(chain, contract, sender, from, to, token, 1)
+}
+
+/// Helper method for transferring an amount of a token between two addresses.
+fn transfer_amount(
+    chain: &mut Chain,
+    contract: ContractAddress,
+    sender: AccountAddress,
+    from: Address,
+    to: Address,
+    token: u32,
+    amount: u64,
+) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+    let to = match to {
+        Address::Account(account) => Receiver::Account(account),
+        Address::Contract(address) => Receiver::Contract(
+            address,
+            OwnedEntrypointName::new_unchecked("onReceivingCIS2".to_string()),
+        ),
+    };
+    update(
+        chain,
+        contract,
+        sender,
+        "transfer",
+        &TransferParams::<TokenIdU32, TokenAmountU64>(vec![Transfer {
+            token_id: TokenIdU32(token),
+            amount: TokenAmountU64(amount),
+            from,
+            to,
+            data: AdditionalData::empty(),
+        }]),
+    )
+}
+
+/// Helper method for adding `operator` as an operator of `owner`.
+fn add_operator(
+    chain: &mut Chain,
+    contract: ContractAddress,
+    owner: AccountAddress,
+    operator: Address,
+) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+    update(
+        chain,
+        contract,
+        owner,
+        "updateOperator",
+        &UpdateOperatorParams(vec![UpdateOperator {
+            update: OperatorUpdate::Add,
+            operator,
+        }]),
+    )
+}
+
+/// Helper method for querying the balance of a single token of an address.
+fn balance_of(
+    chain: &Chain,
+    contract: ContractAddress,
+    address: Address,
+    token: u32,
+) -> TokenAmountU64 {
+    let response: BalanceOfQueryResponse<TokenAmountU64> =
+        view(chain, contract, "balanceOf", &BalanceOfQueryParams {
+            queries: vec![BalanceOfQuery {
+                token_id: TokenIdU32(token),
+                address,
+            }],
+        });
+    response.0[0]
+}
+
+/// Test that `pendingOperations` lists every queued operation with its
+/// earliest execution time.
+#[test]
+fn test_pending_operations() {
+    let (mut chain, contract) = initialize_license();
+
+    let first = TimelockOperation::Upgrade(UpgradeParams {
+        module:  ModuleReference::from([1u8; 32]),
+        migrate: None,
+    });
+    let second = TimelockOperation::Upgrade(UpgradeParams {
+        module:  ModuleReference::from([2u8; 32]),
+        migrate: None,
+    });
+
+    let first_hash: HashSha2256 = update(&mut chain, contract, ALICE, "queueOperation", &first)
+        .expect("Queue first operation")
+        .parse_return_value()
+        .expect("Deserialize hash");
+    chain.tick_block_time(Duration::from_hours(1)).expect("Block time does not overflow");
+    let second_hash: HashSha2256 = update(&mut chain, contract, ALICE, "queueOperation", &second)
+        .expect("Queue second operation")
+        .parse_return_value()
+        .expect("Deserialize hash");
+
+    let mut pending: Vec<PendingOperation> = view(&chain, contract, "pendingOperations", &());
+    pending.sort_by_key(|operation| operation.earliest_execution);
+    assert_eq!(pending, [
+        PendingOperation {
+            operation_hash:     first_hash,
+            earliest_execution: Timestamp::from_timestamp_millis(0)
+                .checked_add(Duration::from_days(2))
+                .unwrap(),
+        },
+        PendingOperation {
+            operation_hash:     second_hash,
+            earliest_execution: Timestamp::from_timestamp_millis(0)
+                .checked_add(Duration::from_hours(49))
+                .unwrap(),
+        },
+    ]);
+}
+
+/// Test minting 10 tokens to one address in a single `mintBatch` call.
+#[test]
+fn test_mint_batch() {
+    let (mut chain, contract) = initialize_license();
+
+    let tokens = (1..=10).map(|id| (TokenIdU32(id), "@license".to_string())).collect();
+    update(&mut chain, contract, ALICE, "mintBatch", &MintBatchParams {
+        owner: BOB,
+        tokens,
+    })
+    .expect("Mint batch");
+
+    let state: ViewState = view(&chain, contract, "view", &());
+    let mut all_tokens: Vec<u32> = state.all_tokens.iter().map(|token| token.0).collect();
+    all_tokens.sort();
+    assert_eq!(all_tokens, (1..=10).collect::<Vec<_>>());
+
+    let (_, bob_state) =
+        state.state.iter().find(|(address, _)| *address == BOB_ADDR).expect("Bob holds tokens");
+    assert_eq!(bob_state.owned_tokens.len(), 10);
+}
+
+/// Test that `mintBatch` rejects batches exceeding the log limit and
+/// unauthorized senders.
+#[test]
+fn test_mint_batch_rejects() {
+    let (mut chain, contract) = initialize_license();
+
+    let tokens: Vec<_> = (1..=17).map(|id| (TokenIdU32(id), "@license".to_string())).collect();
+    let rejected = update(&mut chain, contract, ALICE, "mintBatch", &MintBatchParams {
+        owner:  BOB,
+        tokens: tokens.clone(),
+    })
+    .expect_err("Batch exceeds the log limit");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::LogFull));
+
+    update(&mut chain, contract, BOB, "mintBatch", &MintBatchParams {
+        owner:  BOB,
+        tokens: tokens[..1].to_vec(),
+    })
+    .expect_err("Non-minter cannot mint");
+}
+
+/// Test minting a fungible license pool, transferring partial amounts and
+/// that the balances sum up to the minted supply.
+#[test]
+fn test_fungible_pool() {
+    let (mut chain, contract) = initialize_license();
+
+    update(&mut chain, contract, ALICE, "mintFungible", &MintFungibleParams {
+        owner:  BOB,
+        token:  TokenIdU32(100),
+        amount: TokenAmountU64(50),
+    })
+    .expect("Mint fungible pool");
+
+    transfer_amount(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 100, 20)
+        .expect("Transfer partial amount");
+    transfer_amount(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 100, 31)
+        .expect_err("Transfer more than the balance");
+
+    let bob = balance_of(&chain, contract, BOB_ADDR, 100);
+    let charlie = balance_of(&chain, contract, CHARLIE_ADDR, 100);
+    assert_eq!(bob, TokenAmountU64(30));
+    assert_eq!(charlie, TokenAmountU64(20));
+    assert_eq!(bob.0 + charlie.0, 50);
+
+    // A fungible pool cannot be reused as an NFT and vice versa.
+    mint(&mut chain, contract, BOB, 100).expect_err("Token ID is a fungible pool");
+    mint(&mut chain, contract, BOB, 1).expect("Mint NFT");
+    update(&mut chain, contract, ALICE, "mintFungible", &MintFungibleParams {
+        owner:  BOB,
+        token:  TokenIdU32(1),
+        amount: TokenAmountU64(5),
+    })
+    .expect_err("Token ID is an NFT");
+}
+
+/// Test that fungible mints are subject to the same mint cap, recipient checks
+/// and reserved ranges as mints of NFTs, and count towards `balanceCountOf`.
+#[test]
+fn test_fungible_mint_checks() {
+    let (mut chain, contract) = initialize_license_with(InitParams {
+        per_account_mint_cap: Some(1),
+        ..Default::default()
+    });
+    let mint_fungible = |chain: &mut Chain, sender: AccountAddress, owner, token| {
+        update(chain, contract, sender, "mintFungible", &MintFungibleParams {
+            owner,
+            token: TokenIdU32(token),
+            amount: TokenAmountU64(10),
+        })
+    };
+    let error_of = |rejected: ContractInvokeError| -> ContractError {
+        rejected.parse_return_value().expect("Deserialize error")
+    };
+
+    mint_fungible(&mut chain, ALICE, BOB, 100).expect("Mint fungible pool");
+    let rejected = mint_fungible(&mut chain, ALICE, BOB, 100).expect_err("Over the cap");
+    assert_eq!(error_of(rejected), ContractError::Custom(CustomContractError::MintQuotaExceeded));
+
+    update(&mut chain, contract, ALICE, "setFrozen", &SetFrozenParams {
+        address: CHARLIE_ADDR,
+        frozen:  true,
+    })
+    .expect("Freeze Charlie");
+    let rejected = mint_fungible(&mut chain, ALICE, CHARLIE, 100).expect_err("Frozen recipient");
+    assert_eq!(error_of(rejected), ContractError::Custom(CustomContractError::AddressFrozen));
+
+    update(&mut chain, contract, ALICE, "grantRole", &RoleParams {
+        address: BOB_ADDR,
+        role:    Role::Minter,
+    })
+    .expect("Grant minter role");
+    update(&mut chain, contract, ALICE, "reserveTokenRange", &ReserveTokenRangeParams {
+        minter: BOB_ADDR,
+        range:  Some((1, 50)),
+    })
+    .expect("Reserve range for Bob");
+    let rejected = mint_fungible(&mut chain, BOB, ALICE, 100).expect_err("Out of range");
+    assert_eq!(error_of(rejected), ContractError::Custom(CustomContractError::TokenIdOutOfRange));
+
+    // A fungible token counts once while its balance is not zero.
+    let counts = |chain: &Chain| -> Vec<u32> {
+        view(chain, contract, "balanceCountOf", &vec![BOB_ADDR, ALICE_ADDR])
+    };
+    assert_eq!(counts(&chain), [1, 0]);
+    transfer_amount(&mut chain, contract, BOB, BOB_ADDR, ALICE_ADDR, 100, 4).expect("Transfer");
+    assert_eq!(counts(&chain), [1, 1]);
+    transfer_amount(&mut chain, contract, BOB, BOB_ADDR, ALICE_ADDR, 100, 6).expect("Transfer");
+    assert_eq!(counts(&chain), [0, 1]);
+}
+
+/// Test that a metadata hash provided at mint round-trips through the
+/// `TokenMetadata` event and the `tokenMetadata` query.
+#[test]
+fn test_mint_with_metadata_hash() {
+    let (mut chain, contract) = initialize_license();
+    let hash = [7u8; 32];
+
+    let minted = update(&mut chain, contract, ALICE, "mint", &MintParams {
+        owner:        BOB,
+        token:        TokenIdU32(1),
+        web3id:       "@license".to_string(),
+        hash:         Some(hash),
+        content_type: None,
+        expiry:       None,
+        tier:         0,
+    })
+    .expect("Mint with hash");
+
+    let logged_hash = minted
+        .events()
+        .flat_map(|(_, events)| events.iter())
+        .find_map(|event| match event.parse::<Cis2Event<TokenIdU32, TokenAmountU64>>() {
+            Ok(Cis2Event::TokenMetadata(metadata)) => Some(metadata.metadata_url.hash),
+            _ => None,
+        })
+        .expect("TokenMetadata event logged");
+    assert_eq!(logged_hash, Some(hash));
+
+    assert_eq!(token_metadata(&chain, contract, 1).hash, Some(hash));
+
+    // Without a hash the metadata carries the checksum of the license.
+    mint(&mut chain, contract, BOB, 2).expect("Mint without hash");
+    let checksum: [u8; 32] = view(&chain, contract, "computeChecksum", &ComputeChecksumParams {
+        token_id: TokenIdU32(2),
+        web3id:   "@license".to_string(),
+    });
+    assert_eq!(token_metadata(&chain, contract, 2).hash, Some(checksum));
+}
+
+/// Helper method for querying the metadata URL of a single token.
+fn token_metadata(chain: &Chain, contract: ContractAddress, token: u32) -> MetadataUrl {
+    let response: TokenMetadataQueryResponse =
+        view(chain, contract, "tokenMetadata", &TokenMetadataQueryParams {
+            queries: vec![TokenIdU32(token)],
+        });
+    response.0[0].clone()
+}
+
+/// Test that `tokenTotalSupply` reflects mints and burns of an edition.
+#[test]
+fn test_token_total_supply() {
+    let (mut chain, contract) = initialize_license();
+
+    update(&mut chain, contract, ALICE, "mintFungible", &MintFungibleParams {
+        owner:  BOB,
+        token:  TokenIdU32(100),
+        amount: TokenAmountU64(5),
+    })
+    .expect("Mint edition of 5");
+
+    update(&mut chain, contract, BOB, "burn", &BurnParams {
+        token_id: TokenIdU32(100),
+        owner:    BOB_ADDR,
+        amount:   TokenAmountU64(1),
+        reason:   BurnReason::OwnerRequested,
+    })
+    .expect("Burn one");
+
+    let supply: TokenAmountU64 = view(&chain, contract, "tokenTotalSupply", &TokenIdU32(100));
+    assert_eq!(supply, TokenAmountU64(4));
+}
+
+/// Test that the `totalSupply` counter stays consistent across mint, burn and
+/// a failed mint of a duplicate token ID.
+#[test]
+fn test_total_supply_counter() {
+    let (mut chain, contract) = initialize_license();
+
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    mint(&mut chain, contract, BOB, 2).expect("Mint token 2");
+    mint(&mut chain, contract, BOB, 2).expect_err("Duplicate token ID");
+    assert_eq!(view::<_, u64>(&chain, contract, "totalSupply", &()), 2);
+
+    update(&mut chain, contract, BOB, "burn", &BurnParams {
+        token_id: TokenIdU32(1),
+        owner:    BOB_ADDR,
+        amount:   TokenAmountU64(1),
+        reason:   BurnReason::OwnerRequested,
+    })
+    .expect("Burn token 1");
+    assert_eq!(view::<_, u64>(&chain, contract, "totalSupply", &()), 1);
+
+    let state: ViewState = view(&chain, contract, "view", &());
+    assert_eq!(state.all_tokens.len(), 1);
+}
+
+/// Test that a content type supplied at mint is returned by
+/// `tokenMetadataExt` while `tokenMetadata` stays unchanged.
+#[test]
+fn test_token_metadata_ext_content_type() {
+    let (mut chain, contract) = initialize_license();
+
+    update(&mut chain, contract, ALICE, "mint", &MintParams {
+        owner:        BOB,
+        token:        TokenIdU32(1),
+        web3id:       "@license".to_string(),
+        hash:         None,
+        content_type: Some("application/json".to_string()),
+        expiry:       None,
+        tier:         0,
+    })
+    .expect("Mint with content type");
+    mint(&mut chain, contract, BOB, 2).expect("Mint without content type");
+
+    let metadata: Vec<TokenMetadata> =
+        view(&chain, contract, "tokenMetadataExt", &TokenMetadataQueryParams {
+            queries: vec![TokenIdU32(1), TokenIdU32(2)],
+        });
+    assert_eq!(metadata[0].content_type.as_deref(), Some("application/json"));
+    assert_eq!(metadata[1].content_type, None);
+    assert_eq!(metadata[0].url, token_metadata(&chain, contract, 1).url);
+}
+
+/// Test that the holder can burn their own token and the `Burn` event names
+/// the holder.
+#[test]
+fn test_burn_by_owner() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    let burned = burn(&mut chain, contract, BOB, BOB_ADDR, 1).expect("Owner burns");
+    assert_eq!(burn_event_owner(&burned), BOB_ADDR);
+}
+
+/// Test that an operator can burn on behalf of the holder and the `Burn`
+/// event still names the holder rather than the operator.
+#[test]
+fn test_burn_by_operator() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    add_operator(&mut chain, contract, BOB, CHARLIE_ADDR).expect("Add operator");
+
+    let burned = burn(&mut chain, contract, CHARLIE, BOB_ADDR, 1).expect("Operator burns");
+    assert_eq!(burn_event_owner(&burned), BOB_ADDR);
+}
+
+/// Test that an address which is neither holder nor operator cannot burn.
+#[test]
+fn test_burn_unauthorized() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    let rejected = burn(&mut chain, contract, CHARLIE, BOB_ADDR, 1).expect_err("Unauthorized");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Unauthorized);
+    assert_eq!(balance_of(&chain, contract, BOB_ADDR, 1), TokenAmountU64(1));
+}
+
+/// Helper method for burning a single token held by `owner`.
+fn burn(
+    chain: &mut Chain,
+    contract: ContractAddress,
+    sender: AccountAddress,
+    owner: Address,
+    token: u32,
+) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+    update(chain, contract, sender, "burn", &BurnParams {
+        token_id: TokenIdU32(token),
+        owner,
+        amount: TokenAmountU64(1),
+        reason: BurnReason::OwnerRequested,
+    })
+}
+
+/// Helper method for extracting the owner from the logged `Burn` event.
+fn burn_event_owner(update: &ContractInvokeSuccess) -> Address {
+    update
+        .events()
+        .flat_map(|(_, events)| events.iter())
+        .find_map(|event| match event.parse::<Cis2Event<TokenIdU32, TokenAmountU64>>() {
+            Ok(Cis2Event::Burn(burn)) => Some(burn.owner),
+            _ => None,
+        })
+        .expect("Burn event logged")
+}
+
+/// Test that pausing only minting keeps transfers working.
+#[test]
+fn test_paused_ops_mint_only() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    update(&mut chain, contract, BOB, "setPausedOps", &PAUSE_MINT).expect_err("Non-owner");
+    update(&mut chain, contract, ALICE, "setPausedOps", &PAUSE_MINT).expect("Pause minting");
+
+    let rejected = mint(&mut chain, contract, BOB, 2).expect_err("Minting is paused");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::OperationPaused));
+
+    transfer(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 1).expect("Transfers still work");
+
+    update(&mut chain, contract, ALICE, "setPausedOps", &0u32).expect("Unpause");
+    mint(&mut chain, contract, BOB, 2).expect("Minting resumes");
+}
+
+/// Test that a non-owner upgrade call rejects instead of silently succeeding,
+/// and that a successful upgrade logs an `Upgraded` event.
+#[test]
+fn test_upgrade_authorization_and_event() {
+    let (mut chain, contract) = initialize_license();
+
+    let params = UpgradeParams {
+        module:  module_reference(),
+        migrate: None,
+    };
+    let operation = TimelockOperation::Upgrade(UpgradeParams {
+        module:  params.module,
+        migrate: None,
+    });
+    update(&mut chain, contract, ALICE, "queueOperation", &operation).expect("Queue upgrade");
+    chain.tick_block_time(Duration::from_days(2)).expect("Block time does not overflow");
+
+    update(&mut chain, contract, BOB, "upgrade", &params).expect_err("Non-owner upgrade rejects");
+
+    let upgraded = update(&mut chain, contract, ALICE, "upgrade", &params).expect("Owner upgrades");
+    let events: Vec<CustomEvent> = upgraded
+        .events()
+        .flat_map(|(_, events)| events.iter().filter_map(|e| e.parse().ok()))
+        .collect();
+    assert_eq!(events, [CustomEvent::Upgraded(UpgradedEvent {
+        module: params.module,
+    })]);
+}
+
+/// Test that `gateInfo` aggregates the holder and expiry of several licenses
+/// and rejects unknown tokens.
+#[test]
+fn test_gate_info() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    mint(&mut chain, contract, CHARLIE, 2).expect("Mint token 2");
+
+    let info: Vec<GateInfo> =
+        view(&chain, contract, "gateInfo", &vec![TokenIdU32(1), TokenIdU32(2)]);
+    assert_eq!(info, [
+        GateInfo {
+            owner:   Some(BOB_ADDR),
+            expiry:  None,
+            expired: false,
+        },
+        GateInfo {
+            owner:   Some(CHARLIE_ADDR),
+            expiry:  None,
+            expired: false,
+        },
+    ]);
+
+    chain
+        .contract_invoke(ALICE, ALICE_ADDR, Energy::from(100_000), UpdateContractPayload {
+            address:      contract,
+            amount:       Amount::zero(),
+            receive_name: receive_name("gateInfo"),
+            message:      OwnedParameter::from_serial(&vec![TokenIdU32(3)])
+                .expect("Parameter within size bounds"),
+        })
+        .expect_err("Unknown token rejects");
+}
+
+/// Test that a paused token cannot be transferred or burned until it is
+/// unpaused again.
+#[test]
+fn test_pause_token() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    let pause = SetPausedParams {
+        token_id: TokenIdU32(1),
+        paused:   true,
+    };
+    update(&mut chain, contract, BOB, "setPaused", &pause).expect_err("Non-owner cannot pause");
+    update(&mut chain, contract, ALICE, "setPaused", &pause).expect("Pause token 1");
+    assert!(view::<_, bool>(&chain, contract, "isPaused", &TokenIdU32(1)));
+
+    let rejected = transfer(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 1)
+        .expect_err("Paused token cannot be transferred");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::TokenPaused));
+    burn(&mut chain, contract, BOB, BOB_ADDR, 1).expect_err("Paused token cannot be burned");
+
+    update(&mut chain, contract, ALICE, "setPaused", &SetPausedParams {
+        token_id: TokenIdU32(1),
+        paused:   false,
+    })
+    .expect("Unpause token 1");
+    assert!(!view::<_, bool>(&chain, contract, "isPaused", &TokenIdU32(1)));
+    transfer(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 1).expect("Transfer succeeds");
+}
+
+/// Test that the `SponsoredExecuted` event carries the nonce under its own
+/// tag, distinct from the CIS2 event tags.
+#[test]
+fn test_sponsored_executed_event_serialization() {
+    let event = CustomEvent::SponsoredExecuted(SponsoredExecutedEvent {
+        signer: BOB,
+        nonce:  7,
+        action: SponsoredAction::Transfer,
+    });
+    let bytes = to_bytes(&event);
+    assert_eq!(bytes[0], 248);
+
+    let parsed: CustomEvent = from_bytes(&bytes).expect("Deserialize event");
+    assert_eq!(parsed, event);
+}
+
+/// Test that minting, transfers and burns reject while the contract is paused,
+/// queries keep working, and everything resumes after unpausing.
+#[test]
+fn test_contract_pause() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    mint(&mut chain, contract, BOB, 2).expect("Mint token 2");
+
+    update(&mut chain, contract, BOB, "pause", &()).expect_err("Non-owner cannot pause");
+    update(&mut chain, contract, ALICE, "pause", &()).expect("Pause contract");
+
+    let paused = ContractError::Custom(CustomContractError::ContractPaused);
+    let rejected = mint(&mut chain, contract, BOB, 3).expect_err("Mint rejects");
+    assert_eq!(rejected.parse_return_value::<ContractError>().expect("Error"), paused);
+    let rejected = transfer(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 1)
+        .expect_err("Transfer rejects");
+    assert_eq!(rejected.parse_return_value::<ContractError>().expect("Error"), paused);
+    let rejected = burn(&mut chain, contract, BOB, BOB_ADDR, 2).expect_err("Burn rejects");
+    assert_eq!(rejected.parse_return_value::<ContractError>().expect("Error"), paused);
+
+    assert_eq!(balance_of(&chain, contract, BOB_ADDR, 1), TokenAmountU64(1));
+
+    update(&mut chain, contract, ALICE, "unpause", &()).expect("Unpause contract");
+    mint(&mut chain, contract, BOB, 3).expect("Mint resumes");
+    transfer(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 1).expect("Transfer resumes");
+    burn(&mut chain, contract, BOB, BOB_ADDR, 2).expect("Burn resumes");
+}
+
+/// Test that under the accounts-only restriction accounts can still receive
+/// tokens while contracts are rejected as recipients.
+#[test]
+fn test_accounts_only_holders() {
+    let (mut chain, contract) = initialize_license_with(InitParams {
+        accounts_only: true,
+        ..Default::default()
+    });
+
+    // Mint recipients are always accounts.
+    mint(&mut chain, contract, BOB, 1).expect("Mint to an account");
+    transfer(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 1).expect("Transfer to account");
+
+    let to_contract = Address::Contract(contract);
+    let rejected = transfer(&mut chain, contract, CHARLIE, CHARLIE_ADDR, to_contract, 1)
+        .expect_err("Transfer to a contract");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::ContractHoldersForbidden));
+}
+
+/// Test that minting requires the `Minter` role and that only an `Admin` can
+/// grant and revoke it.
+#[test]
+fn test_minter_role() {
+    let (mut chain, contract) = initialize_license();
+    let minter = RoleParams {
+        address: BOB_ADDR,
+        role:    Role::Minter,
+    };
+
+    mint_as(&mut chain, contract, BOB, BOB, 1).expect_err("Bob is not a minter");
+    update(&mut chain, contract, BOB, "grantRole", &minter).expect_err("Bob is not an admin");
+
+    update(&mut chain, contract, ALICE, "grantRole", &minter).expect("Admin grants minter");
+    mint_as(&mut chain, contract, BOB, BOB, 1).expect("Minter mints");
+
+    update(&mut chain, contract, ALICE, "revokeRole", &minter).expect("Admin revokes minter");
+    mint_as(&mut chain, contract, BOB, BOB, 2).expect_err("Revoked minter cannot mint");
+}
+
+/// Test that a `Burner` can burn tokens of other holders.
+#[test]
+fn test_burner_role() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    burn(&mut chain, contract, CHARLIE, BOB_ADDR, 1).expect_err("Charlie is not a burner");
+    update(&mut chain, contract, ALICE, "grantRole", &RoleParams {
+        address: CHARLIE_ADDR,
+        role:    Role::Burner,
+    })
+    .expect("Grant burner");
+    burn(&mut chain, contract, CHARLIE, BOB_ADDR, 1).expect("Burner burns Bob's token");
+}
+
+/// Test that pausing requires the `Pauser` role.
+#[test]
+fn test_pauser_role() {
+    let (mut chain, contract) = initialize_license();
+
+    update(&mut chain, contract, BOB, "pause", &()).expect_err("Bob is not a pauser");
+    update(&mut chain, contract, ALICE, "grantRole", &RoleParams {
+        address: BOB_ADDR,
+        role:    Role::Pauser,
+    })
+    .expect("Grant pauser");
+    update(&mut chain, contract, BOB, "pause", &()).expect("Pauser pauses");
+    update(&mut chain, contract, BOB, "unpause", &()).expect("Pauser unpauses");
+}
+
+/// Test that a granted `Admin` can manage roles and a revoked one cannot.
+#[test]
+fn test_admin_role() {
+    let (mut chain, contract) = initialize_license();
+    let admin = RoleParams {
+        address: BOB_ADDR,
+        role:    Role::Admin,
+    };
+    let minter = RoleParams {
+        address: CHARLIE_ADDR,
+        role:    Role::Minter,
+    };
+
+    update(&mut chain, contract, ALICE, "grantRole", &admin).expect("Grant admin");
+    update(&mut chain, contract, BOB, "grantRole", &minter).expect("New admin grants roles");
+    mint_as(&mut chain, contract, CHARLIE, CHARLIE, 1).expect("Charlie mints");
+
+    update(&mut chain, contract, ALICE, "revokeRole", &admin).expect("Revoke admin");
+    update(&mut chain, contract, BOB, "revokeRole", &minter).expect_err("Bob is no admin");
+}
+
+/// Helper method for previewing the metadata URL of a token.
+fn preview_metadata_url(
+    chain: &Chain,
+    contract: ContractAddress,
+    token: u32,
+    web3id: Option<&str>,
+) -> String {
+    view(chain, contract, "previewMetadataUrl", &PreviewMetadataUrlParams {
+        token_id: TokenIdU32(token),
+        web3id:   web3id.map(str::to_string),
+    })
+}
+
+/// Test that the previewed metadata URL matches the one stored when a license
+/// is minted and when a fungible pool is created.
+#[test]
+fn test_preview_metadata_url() {
+    let (mut chain, contract) = initialize_license();
+
+    let preview = preview_metadata_url(&chain, contract, 1, Some("@license"));
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    assert_eq!(preview, token_metadata(&chain, contract, 1).url);
+
+    let preview = preview_metadata_url(&chain, contract, 42, None);
+    update(&mut chain, contract, ALICE, "mintFungible", &MintFungibleParams {
+        owner:  BOB,
+        token:  TokenIdU32(42),
+        amount: TokenAmountU64(5),
+    })
+    .expect("Mint pool 42");
+    assert_eq!(preview, token_metadata(&chain, contract, 42).url);
+}
+
+/// Test paging through 50 tokens of one address in chunks of 10.
+#[test]
+fn test_tokens_of_pagination() {
+    let (mut chain, contract) = initialize_license();
+    // Mint in descending order to make sure the pages are sorted.
+    for token in (1..=50).rev() {
+        mint(&mut chain, contract, BOB, token).expect("Mint token");
+    }
+
+    let mut all = Vec::new();
+    for page in 0..5 {
+        let response: TokensOfResponse = view(&chain, contract, "tokensOf", &TokensOfParams {
+            address: BOB_ADDR,
+            start:   page * 10,
+            limit:   10,
+        });
+        assert_eq!(response.tokens.len(), 10);
+        assert_eq!(response.has_more, page < 4);
+        all.extend(response.tokens.iter().map(|token| token.0));
+    }
+    assert_eq!(all, (1..=50).collect::<Vec<_>>());
+
+    let beyond: TokensOfResponse = view(&chain, contract, "tokensOf", &TokensOfParams {
+        address: BOB_ADDR,
+        start:   50,
+        limit:   10,
+    });
+    assert_eq!(beyond, TokensOfResponse {
+        tokens:   Vec::new(),
+        has_more: false,
+    });
+}
+
+/// Test that `tokenOwner` follows transfers and burns.
+#[test]
+fn test_token_owner() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    mint(&mut chain, contract, BOB, 2).expect("Mint token 2");
+
+    transfer(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 1).expect("Transfer token 1");
+    burn(&mut chain, contract, BOB, BOB_ADDR, 2).expect("Burn token 2");
+
+    let owners: Vec<Option<Address>> = view(&chain, contract, "tokenOwner", &TokenOwnerQueryParams {
+        queries: vec![TokenIdU32(1), TokenIdU32(2), TokenIdU32(3)],
+    });
+    assert_eq!(owners, [Some(CHARLIE_ADDR), None, None]);
+}
+
+/// Test that `purgeOperator` removes an operator from every owner.
+#[test]
+fn test_purge_operator() {
+    let (mut chain, contract) = initialize_license();
+    let operator = Address::Contract(ContractAddress::new(99, 0));
+    add_operator(&mut chain, contract, BOB, operator).expect("Bob adds operator");
+    add_operator(&mut chain, contract, CHARLIE, operator).expect("Charlie adds operator");
+    update(&mut chain, contract, ALICE, "addGlobalOperator", &operator)
+        .expect("Add global operator");
+    mint(&mut chain, contract, ALICE, 1).expect("Mint token 1");
+    update(&mut chain, contract, ALICE, "updateScopedOperator", &UpdateScopedOperatorParams {
+        update: OperatorUpdate::Add,
+        operator,
+        token_id: TokenIdU32(1),
+    })
+    .expect("Add scoped operator");
+
+    let params = PurgeOperatorParams {
+        operator,
+        cursor: None,
+    };
+    update(&mut chain, contract, BOB, "purgeOperator", &params).expect_err("Only the owner");
+
+    let purged = update(&mut chain, contract, ALICE, "purgeOperator", &params).expect("Purge");
+    let next_cursor: Option<Address> = purged.parse_return_value().expect("Deserialize cursor");
+    assert_eq!(next_cursor, None);
+    let events: Vec<ContractEvent> = purged
+        .events()
+        .flat_map(|(_, events)| events.iter().map(|e| e.parse().expect("Deserialize event")))
+        .collect();
+    assert_eq!(events.len(), 4);
+    assert_eq!(
+        events[0],
+        ContractEvent::Custom(CustomEvent::GlobalOperatorUpdated(GlobalOperatorUpdatedEvent {
+            operator,
+            update: OperatorUpdate::Remove,
+        }))
+    );
+    assert!(events.contains(&ContractEvent::Custom(CustomEvent::ScopedOperatorUpdated(
+        ScopedOperatorUpdatedEvent {
+            owner: ALICE_ADDR,
+            operator,
+            token_id: TokenIdU32(1),
+            update: OperatorUpdate::Remove,
+        }
+    ))));
+    let globals: Vec<Address> = view(&chain, contract, "globalOperators", &());
+    assert!(!globals.contains(&operator));
+    let graph: OperatorGraph = view(&chain, contract, "operatorGraph", &ALICE_ADDR);
+    assert!(graph.scoped.is_empty());
+
+    let response: OperatorOfQueryResponse =
+        view(&chain, contract, "operatorOf", &OperatorOfQueryParams {
+            queries: vec![
+                OperatorOfQuery {
+                    owner:   BOB_ADDR,
+                    address: operator,
+                },
+                OperatorOfQuery {
+                    owner:   CHARLIE_ADDR,
+                    address: operator,
+                },
+            ],
+        });
+    assert_eq!(response.0, [false, false]);
+}
+
+/// Test that a purge exceeding the log budget resumes at the returned address
+/// and skips no owner, even when addresses are added in between.
+#[test]
+fn test_purge_operator_resumes() {
+    let (mut chain, contract) = initialize_license();
+    let operator = Address::Contract(ContractAddress::new(99, 0));
+    let owners: Vec<AccountAddress> = (0..40u8).map(|i| AccountAddress([10 + i; 32])).collect();
+    for owner in &owners {
+        chain.create_account(Account::new(*owner, ACC_INITIAL_BALANCE));
+        add_operator(&mut chain, contract, *owner, operator).expect("Add operator");
+    }
+
+    let purged = update(&mut chain, contract, ALICE, "purgeOperator", &PurgeOperatorParams {
+        operator,
+        cursor: None,
+    })
+    .expect("First purge");
+    let cursor: Option<Address> = purged.parse_return_value().expect("Deserialize cursor");
+    assert!(cursor.is_some());
+
+    // A new address does not shift the cursor.
+    mint(&mut chain, contract, AccountAddress([5u8; 32]), 1).expect("Mint to a new address");
+
+    let purged = update(&mut chain, contract, ALICE, "purgeOperator", &PurgeOperatorParams {
+        operator,
+        cursor,
+    })
+    .expect("Second purge");
+    let cursor: Option<Address> = purged.parse_return_value().expect("Deserialize cursor");
+    assert_eq!(cursor, None);
+
+    let response: OperatorOfQueryResponse =
+        view(&chain, contract, "operatorOf", &OperatorOfQueryParams {
+            queries: owners
+                .iter()
+                .map(|owner| OperatorOfQuery {
+                    owner:   Address::Account(*owner),
+                    address: operator,
+                })
+                .collect(),
+        });
+    assert!(response.0.iter().all(|is_operator| !is_operator));
+}
+
+/// Test that with required registration only registered recipients can be
+/// minted to.
+#[test]
+fn test_registered_recipients_only() {
+    let (mut chain, contract) = initialize_license_with(InitParams {
+        registered_recipients_only: true,
+        ..Default::default()
+    });
+
+    let rejected = mint(&mut chain, contract, BOB, 1).expect_err("Bob is not registered");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::RecipientNotRegistered));
+
+    update(&mut chain, contract, BOB, "registerAddress", &BOB_ADDR).expect_err("Only the owner");
+    update(&mut chain, contract, ALICE, "registerAddress", &BOB_ADDR).expect("Register Bob");
+    mint(&mut chain, contract, BOB, 1).expect("Bob is registered");
+}
+
+/// Test that `isValid` reports a license as invalid once the block time
+/// passes its expiry, while licenses without expiry stay valid.
+#[test]
+fn test_is_valid_expiry() {
+    let (mut chain, contract) = initialize_license();
+    let expiry = Timestamp::from_timestamp_millis(0).checked_add(Duration::from_days(1)).unwrap();
+    mint_with_expiry(&mut chain, contract, BOB, 1, expiry).expect("Mint expiring license");
+    mint(&mut chain, contract, BOB, 2).expect("Mint perpetual license");
+
+    let tokens = vec![TokenIdU32(1), TokenIdU32(2), TokenIdU32(3)];
+    let valid: Vec<bool> = view(&chain, contract, "isValid", &tokens);
+    assert_eq!(valid, [true, true, false]);
+
+    chain.tick_block_time(Duration::from_days(2)).expect("Block time does not overflow");
+    let valid: Vec<bool> = view(&chain, contract, "isValid", &tokens);
+    assert_eq!(valid, [false, true, false]);
+
+    // Expired licenses can still be transferred.
+    transfer(&mut chain, contract, BOB, BOB_ADDR, ALICE_ADDR, 1).expect("Return expired license");
+}
+
+/// Helper method for minting a license expiring at `expiry` to `owner`.
+fn mint_with_expiry(
+    chain: &mut Chain,
+    contract: ContractAddress,
+    owner: AccountAddress,
+    token: u32,
+    expiry: Timestamp,
+) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+    update(chain, contract, ALICE, "mint", &MintParams {
+        owner,
+        token: TokenIdU32(token),
+        web3id: "@license".to_string(),
+        hash: None,
+        content_type: None,
+        expiry: Some(expiry),
+        tier: 0,
+    })
+}
+
+/// Test that `operatorGraph` lists the operators of an owner.
+#[test]
+fn test_operator_graph() {
+    let (mut chain, contract) = initialize_license();
+    add_operator(&mut chain, contract, BOB, CHARLIE_ADDR).expect("Add Charlie");
+    add_operator(&mut chain, contract, BOB, ALICE_ADDR).expect("Add Alice");
+
+    let graph: OperatorGraph = view(&chain, contract, "operatorGraph", &BOB_ADDR);
+    assert_eq!(graph, OperatorGraph {
+        operators: vec![ALICE_ADDR, CHARLIE_ADDR],
+        scoped:    vec![],
+    });
+
+    let empty: OperatorGraph = view(&chain, contract, "operatorGraph", &CHARLIE_ADDR);
+    assert!(empty.operators.is_empty());
+}
+
+/// Test that renewing an expired license makes it valid again.
+#[test]
+fn test_renew() {
+    let (mut chain, contract) = initialize_license();
+    let expiry = Timestamp::from_timestamp_millis(0).checked_add(Duration::from_days(1)).unwrap();
+    mint_with_expiry(&mut chain, contract, BOB, 1, expiry).expect("Mint expiring license");
+    chain.tick_block_time(Duration::from_days(2)).expect("Block time does not overflow");
+    let valid: Vec<bool> = view(&chain, contract, "isValid", &vec![TokenIdU32(1)]);
+    assert_eq!(valid, [false]);
+
+    let params = RenewParams {
+        token_id:   TokenIdU32(1),
+        new_expiry: expiry.checked_add(Duration::from_days(30)).unwrap(),
+    };
+    update(&mut chain, contract, BOB, "renew", &params).expect_err("Holder cannot renew");
+    let renewed = update(&mut chain, contract, ALICE, "renew", &params).expect("Owner renews");
+
+    let events: Vec<ContractEvent> = renewed
+        .events()
+        .flat_map(|(_, events)| events.iter().map(|e| e.parse().expect("Deserialize event")))
+        .collect();
+    assert!(matches!(&events[..], [
+        ContractEvent::Cis2(Cis2Event::TokenMetadata(metadata)),
+        ContractEvent::Custom(CustomEvent::LicenseRenewed(LicenseRenewedEvent {
+            token_id: TokenIdU32(1),
+            new_expiry,
+        })),
+    ] if metadata.token_id == TokenIdU32(1) && *new_expiry == params.new_expiry));
+    let valid: Vec<bool> = view(&chain, contract, "isValid", &vec![TokenIdU32(1)]);
+    assert_eq!(valid, [true]);
+
+    let missing = update(&mut chain, contract, ALICE, "renew", &RenewParams {
+        token_id:   TokenIdU32(2),
+        new_expiry: params.new_expiry,
+    })
+    .expect_err("Missing license");
+    let error: ContractError = missing.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::LicenseNotFound));
+}
+
+/// Test that holders cannot resell a token during its lockup, while the
+/// owner of the contract can.
+#[test]
+fn test_transfer_lockup() {
+    let (mut chain, contract) = initialize_license();
+    update(&mut chain, contract, BOB, "setLockup", &Duration::from_days(1))
+        .expect_err("Only the owner sets the lockup");
+    update(&mut chain, contract, ALICE, "setLockup", &Duration::from_days(1))
+        .expect("Owner sets the lockup");
+    mint(&mut chain, contract, ALICE, 1).expect("Mint token 1");
+    mint(&mut chain, contract, BOB, 2).expect("Mint token 2");
+
+    transfer(&mut chain, contract, ALICE, ALICE_ADDR, BOB_ADDR, 1).expect("Owner bypasses lockup");
+    let rejected = transfer(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 2)
+        .expect_err("Holder transfers during lockup");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::LockupActive));
+
+    chain.tick_block_time(Duration::from_days(1)).expect("Block time does not overflow");
+    transfer(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 2).expect("Lockup has passed");
+}
+
+/// Test that minted tokens use the metadata base URL configured at init and
+/// updated by the owner.
+#[test]
+fn test_metadata_base_url() {
+    let (mut chain, contract) = initialize_license_with(InitParams {
+        metadata_base_url: " https://licenses.example.com/v1/ ".to_string(),
+        ..Default::default()
+    });
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    let url = token_metadata(&chain, contract, 1).url;
+    assert!(url.starts_with("https://licenses.example.com/v1/"), "Unexpected URL {}", url);
+
+    let new_base = "https://licenses.example.com/v2/".to_string();
+    update(&mut chain, contract, BOB, "updateMetadataBaseUrl", &new_base)
+        .expect_err("Only the owner updates the base URL");
+    update(&mut chain, contract, ALICE, "updateMetadataBaseUrl", &new_base)
+        .expect("Owner updates the base URL");
+    mint(&mut chain, contract, BOB, 2).expect("Mint token 2");
+
+    assert!(token_metadata(&chain, contract, 2).url.starts_with(&new_base));
+    assert_eq!(token_metadata(&chain, contract, 1).url, url);
+}
+
+/// Test that `whoami` returns the address of the instance and its owner.
+#[test]
+fn test_whoami() {
+    let (chain, contract) = initialize_license();
+    let identity: (ContractAddress, Address) = view(&chain, contract, "whoami", &());
+    assert_eq!(identity, (contract, ALICE_ADDR));
+}
+
+/// Test that metadata URLs never contain whitespace around the base URL.
+#[test]
+fn test_metadata_url_without_spaces() {
+    let (mut chain, contract) = initialize_license_with(InitParams {
+        metadata_base_url: " https://web3id.backend.aesirx.io:8001/licenses/\n".to_string(),
+        ..Default::default()
+    });
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    let url = token_metadata(&chain, contract, 1).url;
+    assert!(url.starts_with("https://"), "Unexpected URL {}", url);
+    assert!(!url.contains(char::is_whitespace), "Unexpected URL {}", url);
+    let preview = preview_metadata_url(&chain, contract, 2, None);
+    assert!(!preview.contains(char::is_whitespace), "Unexpected URL {}", preview);
+}
+
+/// Test that the web3id of a license is stored and referenced by its metadata
+/// URL.
+#[test]
+fn test_web3id_of() {
+    let (mut chain, contract) = initialize_license_with(InitParams {
+        metadata_base_url: "https://licenses.example.com/".to_string(),
+        ..Default::default()
+    });
+    update(&mut chain, contract, ALICE, "mint", &MintParams {
+        owner:        BOB,
+        token:        TokenIdU32(1),
+        web3id:       "@aesirx_user1".to_string(),
+        hash:         None,
+        content_type: None,
+        expiry:       None,
+        tier:         0,
+    })
+    .expect("Mint with valid web3id");
+
+    let web3id: Web3Id = view(&chain, contract, "web3IdOf", &TokenIdU32(1));
+    assert_eq!(web3id, "@aesirx_user1");
+    let url = token_metadata(&chain, contract, 1).url;
+    assert_eq!(url, "https://licenses.example.com/@aesirx_user1");
+}
+
+/// Test that malformed web3ids are rejected at mint.
+#[test]
+fn test_mint_invalid_web3id() {
+    let (mut chain, contract) = initialize_license();
+
+    for web3id in ["aesirx", "@ab", "@has space", "@this_web3id_is_too_long"] {
+        let rejected = update(&mut chain, contract, ALICE, "mint", &MintParams {
+            owner:        BOB,
+            token:        TokenIdU32(1),
+            web3id:       web3id.to_string(),
+            hash:         None,
+            content_type: None,
+            expiry:       None,
+            tier:         0,
+        })
+        .expect_err("Malformed web3id");
+        let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+        assert_eq!(error, ContractError::Custom(CustomContractError::InvalidWeb3Id));
+    }
+}
+
+/// Test that `supports` reports the standards implemented by the contract.
+#[test]
+fn test_supports() {
+    let (chain, contract) = initialize_license();
+
+    let query = |id: &str| -> SupportResult {
+        let params = SupportsQueryParams {
+            queries: vec![StandardIdentifierOwned::new_unchecked(id.to_string())],
+        };
+        let response: SupportsQueryResponse = view(&chain, contract, "supports", &params);
+        response.results.into_iter().next().expect("One result")
+    };
+    assert!(matches!(query("CIS-0"), SupportResult::Support));
+    assert!(matches!(query("CIS-2"), SupportResult::Support));
+    assert!(matches!(query("CIS-3"), SupportResult::Support));
+    assert!(matches!(query("CIS-4"), SupportResult::NoSupport));
+}
+
+/// Test that `supportsPermit` lists the entrypoints which can be sponsored.
+#[test]
+fn test_supports_permit() {
+    let (chain, contract) = initialize_license();
+    let params = SupportsPermitQueryParams {
+        queries: ["transfer", "updateOperator", "mint"]
+            .iter()
+            .map(|name| OwnedEntrypointName::new_unchecked(name.to_string()))
+            .collect(),
+    };
+    let response: SupportsQueryResponse = view(&chain, contract, "supportsPermit", &params);
+    assert!(matches!(response.results[..], [
+        SupportResult::Support,
+        SupportResult::Support,
+        SupportResult::NoSupport
+    ]));
+}
+
+/// Test that `supports` reports CIS-3 only while `permit` is not paused.
+#[test]
+fn test_supports_follows_paused_permit() {
+    let (mut chain, contract) = initialize_license();
+    let query = |chain: &Chain| -> SupportResult {
+        let params = SupportsQueryParams {
+            queries: vec![StandardIdentifierOwned::new_unchecked("CIS-3".to_string())],
+        };
+        let response: SupportsQueryResponse = view(chain, contract, "supports", &params);
+        response.results.into_iter().next().expect("One result")
+    };
+    assert!(matches!(query(&chain), SupportResult::Support));
+
+    update(&mut chain, contract, ALICE, "setPausedOps", &PAUSE_PERMIT).expect("Pause permit");
+    assert!(matches!(query(&chain), SupportResult::NoSupport));
+    let params = SupportsPermitQueryParams {
+        queries: vec![OwnedEntrypointName::new_unchecked("transfer".to_string())],
+    };
+    let response: SupportsQueryResponse = view(&chain, contract, "supportsPermit", &params);
+    assert!(matches!(response.results[..], [SupportResult::NoSupport]));
+
+    update(&mut chain, contract, ALICE, "setPausedOps", &0u32).expect("Unpause permit");
+    assert!(matches!(query(&chain), SupportResult::Support));
+}
+
+/// Test the web3id format validation at its boundaries.
+#[test]
+fn test_check_web3id() {
+    // Length boundaries, including the `@`.
+    assert!(!check_web3id("@ab"));
+    assert!(check_web3id("@abc"));
+    assert!(check_web3id(&format!("@{}", "a".repeat(20))));
+    assert!(!check_web3id(&format!("@{}", "a".repeat(21))));
+
+    // Allowed and disallowed characters.
+    assert!(check_web3id("@Aesir_X_2024"));
+    assert!(!check_web3id("@aesir-x"));
+    assert!(!check_web3id("@aesir x"));
+    assert!(!check_web3id("@aesir.x"));
+    assert!(!check_web3id("@aesirx@"));
+    assert!(!check_web3id("@æsirx"));
+
+    // The leading `@` is required.
+    assert!(!check_web3id("aesirx"));
+    assert!(!check_web3id("aesirx@"));
+    assert!(!check_web3id(""));
+}
+
+/// Test that `tokenMetadata` returns the new URL after `updateTokenMetadata`.
+#[test]
+fn test_update_token_metadata() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    let params = UpdateTokenMetadataParams {
+        token_id: TokenIdU32(1),
+        url:      "https://licenses.example.com/moved/1".to_string(),
+        hash:     None,
+    };
+    update(&mut chain, contract, BOB, "updateTokenMetadata", &params)
+        .expect_err("Holder cannot update the metadata");
+    let updated = update(&mut chain, contract, ALICE, "updateTokenMetadata", &params)
+        .expect("Owner updates the metadata");
+
+    let events: Vec<Cis2Event<ContractTokenId, ContractTokenAmount>> = updated
+        .events()
+        .flat_map(|(_, events)| events.iter().map(|e| e.parse().expect("Deserialize event")))
+        .collect();
+    assert!(matches!(&events[..], [Cis2Event::TokenMetadata(event)]
+        if event.metadata_url.url == params.url));
+    assert_eq!(token_metadata(&chain, contract, 1).url, params.url);
+
+    let unknown = UpdateTokenMetadataParams {
+        token_id: TokenIdU32(2),
+        ..params
+    };
+    let missing = update(&mut chain, contract, ALICE, "updateTokenMetadata", &unknown)
+        .expect_err("Unknown token");
+    let error: ContractError = missing.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::InvalidTokenId);
+}
+
+/// A test account with signing keys, created by [`create_signing_account`].
+const DAVE: AccountAddress = AccountAddress([3u8; 32]);
+const DAVE_ADDR: Address = Address::Account(DAVE);
+
+/// Helper method for creating an account whose keys can sign messages.
+fn create_signing_account(chain: &mut Chain, address: AccountAddress) -> AccountKeys {
+    let keys = AccountKeys::singleton(&mut rand::thread_rng());
+    let balance = AccountBalance::new(ACC_INITIAL_BALANCE, Amount::zero(), Amount::zero())
+        .expect("Valid balance");
+    chain.create_account(Account::new_with_keys(address, balance, (&keys).into()));
+    keys
+}
+
+/// Helper method for building a message invoking `entry_point` on `contract`.
+fn permit_message<P: Serial>(
+    contract: ContractAddress,
+    nonce: u64,
+    timestamp: Timestamp,
+    entry_point: &str,
+    payload: &P,
+) -> PermitMessage {
+    PermitMessage {
+        contract_address: contract,
+        nonce,
+        timestamp,
+        entry_point: OwnedEntrypointName::new_unchecked(entry_point.to_string()),
+        payload: to_bytes(payload),
+    }
+}
+
+/// Helper method for building a `permit` parameter signed by `signer`.
+fn sign_permit(
+    chain: &Chain,
+    contract: ContractAddress,
+    keys: &AccountKeys,
+    signer: AccountAddress,
+    message: PermitMessage,
+) -> PermitParams {
+    let mut params = PermitParams {
+        signature: AccountSignatures {
+            sigs: Default::default(),
+        },
+        signer,
+        message,
+    };
+    let message_hash: [u8; 32] = view(chain, contract, "viewMessageHash", &params);
+    params.signature = keys.sign_message(&message_hash);
+    params
+}
+
+/// Test that a sponsor can submit a transfer signed by the token holder, and
+/// that the signed message can neither be replayed nor used after it expired.
+#[test]
+fn test_permit_transfer() {
+    let (mut chain, contract) = initialize_license();
+    let keys = create_signing_account(&mut chain, DAVE);
+    mint(&mut chain, contract, DAVE, 1).expect("Mint token 1");
+    mint(&mut chain, contract, DAVE, 2).expect("Mint token 2");
+
+    let transfer_to_bob = |token: u32| {
+        TransferParams::<TokenIdU32, TokenAmountU64>(vec![Transfer {
+            token_id: TokenIdU32(token),
+            amount:   TokenAmountU64(1),
+            from:     DAVE_ADDR,
+            to:       Receiver::Account(BOB),
+            data:     AdditionalData::empty(),
+        }])
+    };
+    let expiry = Timestamp::from_timestamp_millis(0).checked_add(Duration::from_days(1)).unwrap();
+    let message = permit_message(contract, 0, expiry, "transfer", &transfer_to_bob(1));
+    let params = sign_permit(&chain, contract, &keys, DAVE, message);
+
+    // Charlie sponsors the transfer signed by Dave.
+    let executed = update(&mut chain, contract, CHARLIE, "permit", &params).expect("Permit");
+    assert_eq!(balance_of(&chain, contract, BOB_ADDR, 1), TokenAmountU64(1));
+    let events: Vec<CustomEvent> = executed
+        .events()
+        .flat_map(|(_, events)| events.iter().filter_map(|e| e.parse().ok()))
+        .collect();
+    assert_eq!(events, [CustomEvent::SponsoredExecuted(SponsoredExecutedEvent {
+        signer: DAVE,
+        nonce:  0,
+        action: SponsoredAction::Transfer,
+    })]);
+
+    // Replaying the message is rejected.
+    let replayed = update(&mut chain, contract, CHARLIE, "permit", &params).expect_err("Replay");
+    let error: ContractError = replayed.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::NonceMismatch));
+
+    // Expired messages are rejected.
+    let message = permit_message(contract, 1, expiry, "transfer", &transfer_to_bob(2));
+    let params = sign_permit(&chain, contract, &keys, DAVE, message);
+    chain.tick_block_time(Duration::from_days(2)).expect("Block time does not overflow");
+    let expired = update(&mut chain, contract, CHARLIE, "permit", &params).expect_err("Expired");
+    let error: ContractError = expired.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::Expired));
+
+    let nonces: Vec<u64> = view(&chain, contract, "nonceOf", &NonceOfQueryParams {
+        queries: vec![DAVE],
+    });
+    assert_eq!(nonces, [1]);
+}
+
+/// Test that `permit` rejects messages signed with the wrong keys.
+#[test]
+fn test_permit_wrong_signature() {
+    let (mut chain, contract) = initialize_license();
+    create_signing_account(&mut chain, DAVE);
+    let other_keys = AccountKeys::singleton(&mut rand::thread_rng());
+
+    let expiry = Timestamp::from_timestamp_millis(0).checked_add(Duration::from_days(1)).unwrap();
+    let updates = UpdateOperatorParams(vec![UpdateOperator {
+        update:   OperatorUpdate::Add,
+        operator: BOB_ADDR,
+    }]);
+    let message = permit_message(contract, 0, expiry, "updateOperator", &updates);
+    let params = sign_permit(&chain, contract, &other_keys, DAVE, message);
+    let rejected = update(&mut chain, contract, CHARLIE, "permit", &params).expect_err("Forged");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::WrongSignature));
+}
+
+/// Test that `nonceOf` defaults to 0 and increments with every executed
+/// `permit` of the signer.
+#[test]
+fn test_nonce_of() {
+    let (mut chain, contract) = initialize_license();
+    let keys = create_signing_account(&mut chain, DAVE);
+    let query = NonceOfQueryParams {
+        queries: vec![DAVE, BOB],
+    };
+    let nonces: Vec<u64> = view(&chain, contract, "nonceOf", &query);
+    assert_eq!(nonces, [0, 0]);
+
+    let expiry = Timestamp::from_timestamp_millis(0).checked_add(Duration::from_days(1)).unwrap();
+    for (nonce, operator) in [(0, BOB_ADDR), (1, CHARLIE_ADDR)] {
+        let updates = UpdateOperatorParams(vec![UpdateOperator {
+            update: OperatorUpdate::Add,
+            operator,
+        }]);
+        let message = permit_message(contract, nonce, expiry, "updateOperator", &updates);
+        let params = sign_permit(&chain, contract, &keys, DAVE, message);
+        update(&mut chain, contract, ALICE, "permit", &params).expect("Permit");
+    }
+
+    let nonces: Vec<u64> = view(&chain, contract, "nonceOf", &query);
+    assert_eq!(nonces, [2, 0]);
+    let graph: OperatorGraph = view(&chain, contract, "operatorGraph", &DAVE_ADDR);
+    assert_eq!(graph.operators.len(), 2);
+}
+
+/// Test that `supplyOf` reports 1 for existing licenses and 0 for burned and
+/// unknown tokens.
+#[test]
+fn test_supply_of() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    mint(&mut chain, contract, BOB, 2).expect("Mint token 2");
+    burn(&mut chain, contract, BOB, BOB_ADDR, 2).expect("Burn token 2");
+
+    let supplies: Vec<TokenAmountU64> =
+        view(&chain, contract, "supplyOf", &vec![TokenIdU32(1), TokenIdU32(2), TokenIdU32(3)]);
+    assert_eq!(supplies, [TokenAmountU64(1), TokenAmountU64(0), TokenAmountU64(0)]);
+}
+
+/// Test the default royalty, a per-token override and out-of-range rates.
+#[test]
+fn test_royalty_info() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    mint(&mut chain, contract, BOB, 2).expect("Mint token 2");
+
+    update(&mut chain, contract, ALICE, "setRoyalty", &SetRoyaltyParams {
+        token_id:     None,
+        receiver:     ALICE_ADDR,
+        basis_points: 500,
+    })
+    .expect("Set default royalty");
+    update(&mut chain, contract, ALICE, "setRoyalty", &SetRoyaltyParams {
+        token_id:     Some(TokenIdU32(2)),
+        receiver:     CHARLIE_ADDR,
+        basis_points: 1_000,
+    })
+    .expect("Override royalty of token 2");
+
+    let royalty_of = |token: u32| -> RoyaltyInfo {
+        view(&chain, contract, "royaltyInfo", &RoyaltyInfoParams {
+            token_id:    TokenIdU32(token),
+            sale_amount: Amount::from_ccd(200),
+        })
+    };
+    assert_eq!(royalty_of(1), RoyaltyInfo {
+        receiver:       ALICE_ADDR,
+        royalty_amount: Amount::from_ccd(10),
+    });
+    assert_eq!(royalty_of(2), RoyaltyInfo {
+        receiver:       CHARLIE_ADDR,
+        royalty_amount: Amount::from_ccd(20),
+    });
+
+    let rejected = update(&mut chain, contract, ALICE, "setRoyalty", &SetRoyaltyParams {
+        token_id:     None,
+        receiver:     ALICE_ADDR,
+        basis_points: 10_001,
+    })
+    .expect_err("Rate above 100%");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::InvalidRoyalty));
+}
+
+/// Helper method for building the parameter burning one token of `owner`.
+fn burn_params(owner: Address, token: u32) -> BurnParams {
+    BurnParams {
+        token_id: TokenIdU32(token),
+        owner,
+        amount: TokenAmountU64(1),
+        reason: BurnReason::OwnerRequested,
+    }
+}
+
+/// Test burning three owned tokens in one `burnBatch` call.
+#[test]
+fn test_burn_batch() {
+    let (mut chain, contract) = initialize_license();
+    for token in 1..=3 {
+        mint(&mut chain, contract, BOB, token).expect("Mint token");
+    }
+
+    let burns: Vec<BurnParams> = (1..=3).map(|token| burn_params(BOB_ADDR, token)).collect();
+    let burned = update(&mut chain, contract, BOB, "burnBatch", &burns).expect("Burn batch");
+
+    let events: Vec<ContractEvent> = burned
+        .events()
+        .flat_map(|(_, events)| events.iter().map(|e| e.parse().expect("Deserialize event")))
+        .collect();
+    let burn_events =
+        events.iter().filter(|event| matches!(event, ContractEvent::Cis2(Cis2Event::Burn(_))));
+    assert_eq!(burn_events.count(), 3);
+    let total: u64 = view(&chain, contract, "totalSupply", &());
+    assert_eq!(total, 0);
+}
+
+/// Test that `burnBatch` burns nothing if one of the burns is unauthorized.
+#[test]
+fn test_burn_batch_atomic() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    mint(&mut chain, contract, BOB, 2).expect("Mint token 2");
+    mint(&mut chain, contract, CHARLIE, 3).expect("Mint token 3");
+
+    let burns =
+        vec![burn_params(BOB_ADDR, 1), burn_params(BOB_ADDR, 2), burn_params(CHARLIE_ADDR, 3)];
+    let rejected =
+        update(&mut chain, contract, BOB, "burnBatch", &burns).expect_err("Third is unauthorized");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Unauthorized);
+
+    let total: u64 = view(&chain, contract, "totalSupply", &());
+    assert_eq!(total, 3);
+    assert_eq!(balance_of(&chain, contract, BOB_ADDR, 1), TokenAmountU64(1));
+}
+
+/// Test that admin operations log events which parse back into the combined
+/// `ContractEvent` of the schema.
+#[test]
+fn test_admin_events() {
+    let (mut chain, contract) = initialize_license();
+
+    let logged_events = |result: ContractInvokeSuccess| -> Vec<ContractEvent> {
+        result
+            .events()
+            .flat_map(|(_, events)| events.iter().map(|e| e.parse().expect("Deserialize event")))
+            .collect()
+    };
+
+    let role = RoleParams {
+        address: BOB_ADDR,
+        role:    Role::Minter,
+    };
+    let granted = update(&mut chain, contract, ALICE, "grantRole", &role).expect("Grant role");
+    assert_eq!(logged_events(granted), [ContractEvent::Custom(CustomEvent::RoleGranted(
+        RoleEvent {
+            address: BOB_ADDR,
+            role:    Role::Minter,
+        }
+    ))]);
+    let revoked = update(&mut chain, contract, ALICE, "revokeRole", &role).expect("Revoke role");
+    assert_eq!(logged_events(revoked), [ContractEvent::Custom(CustomEvent::RoleRevoked(
+        RoleEvent {
+            address: BOB_ADDR,
+            role:    Role::Minter,
+        }
+    ))]);
+
+    let paused = update(&mut chain, contract, ALICE, "pause", &()).expect("Pause");
+    assert_eq!(logged_events(paused), [ContractEvent::Custom(CustomEvent::Paused(PauseEvent {
+        pauser:     ALICE_ADDR,
+        block_time: chain.block_time(),
+    }))]);
+    let unpaused = update(&mut chain, contract, ALICE, "unpause", &()).expect("Unpause");
+    assert_eq!(logged_events(unpaused), [ContractEvent::Custom(CustomEvent::Unpaused(
+        PauseEvent {
+            pauser:     ALICE_ADDR,
+            block_time: chain.block_time(),
+        }
+    ))]);
+
+    // CIS2 events parse into the same type.
+    let minted = mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    assert!(matches!(logged_events(minted)[0], ContractEvent::Cis2(Cis2Event::Mint(_))));
+}
+
+/// Test that a batch of 100 `balanceOf` queries against 2 addresses returns
+/// the same balances, in the same order, as querying them one by one.
+#[test]
+fn test_balance_of_batch() {
+    let (mut chain, contract) = initialize_license();
+    for token in 1..=10 {
+        let owner = if token % 2 == 0 { BOB } else { CHARLIE };
+        mint(&mut chain, contract, owner, token).expect("Mint token");
+    }
+
+    let queries: Vec<(Address, u32)> = (0..100)
+        .map(|i| (if i % 3 == 0 { BOB_ADDR } else { CHARLIE_ADDR }, i % 10 + 1))
+        .collect();
+    let params = BalanceOfQueryParams {
+        queries: queries
+            .iter()
+            .map(|(address, token)| BalanceOfQuery {
+                token_id: TokenIdU32(*token),
+                address:  *address,
+            })
+            .collect(),
+    };
+    let batched: BalanceOfQueryResponse<TokenAmountU64> =
+        view(&chain, contract, "balanceOf", &params);
+
+    let naive: Vec<TokenAmountU64> = queries
+        .iter()
+        .map(|(address, token)| balance_of(&chain, contract, *address, *token))
+        .collect();
+    assert_eq!(batched.0, naive);
+}
+
+/// Test that burning a license requires an amount of exactly 1.
+#[test]
+fn test_burn_amount() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    for amount in [0, 2] {
+        let rejected = update(&mut chain, contract, BOB, "burn", &BurnParams {
+            token_id: TokenIdU32(1),
+            owner: BOB_ADDR,
+            amount: TokenAmountU64(amount),
+            reason: BurnReason::OwnerRequested,
+        })
+        .expect_err("Amount other than 1");
+        let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+        assert_eq!(error, ContractError::InsufficientFunds);
+    }
+    assert_eq!(balance_of(&chain, contract, BOB_ADDR, 1), TokenAmountU64(1));
+
+    burn(&mut chain, contract, BOB, BOB_ADDR, 1).expect("Burn amount 1");
+}
+
+/// Test paging through the state of many addresses with `viewPaginated`.
+#[test]
+fn test_view_paginated() {
+    let (mut chain, contract) = initialize_license();
+    for i in 0..12u8 {
+        let owner = AccountAddress([10 + i; 32]);
+        chain.create_account(Account::new(owner, ACC_INITIAL_BALANCE));
+        mint(&mut chain, contract, owner, u32::from(i)).expect("Mint token");
+    }
+
+    let mut seen = Vec::new();
+    for skip in [0, 5, 10] {
+        let page: ViewPage =
+            view(&chain, contract, "viewPaginated", &ViewPaginatedParams { skip, take: 5 });
+        assert_eq!(page.total, 12);
+        assert_eq!(page.state.len(), if skip == 10 { 2 } else { 5 });
+        seen.extend(page.state.into_iter().map(|(address, _)| address));
+    }
+
+    // The pages cover every address exactly once, in the order of `view`.
+    let full: ViewState = view(&chain, contract, "view", &());
+    let all: Vec<Address> = full.state.into_iter().map(|(address, _)| address).collect();
+    assert_eq!(seen, all);
+}
+
+/// Test that minters with reserved ranges can only mint inside their range and
+/// that ranges cannot overlap, except with the range they replace.
+#[test]
+fn test_reserved_token_ranges() {
+    let (mut chain, contract) = initialize_license();
+    for minter in [BOB_ADDR, CHARLIE_ADDR] {
+        update(&mut chain, contract, ALICE, "grantRole", &RoleParams {
+            address: minter,
+            role:    Role::Minter,
+        })
+        .expect("Grant minter role");
+    }
+
+    let reserve = |minter: Address, range: (u32, u32)| ReserveTokenRangeParams {
+        minter,
+        range: Some(range),
+    };
+    update(&mut chain, contract, BOB, "reserveTokenRange", &reserve(BOB_ADDR, (1, 1000)))
+        .expect_err("Only admins reserve ranges");
+    update(&mut chain, contract, ALICE, "reserveTokenRange", &reserve(BOB_ADDR, (1, 100)))
+        .expect("Reserve range for Bob");
+    let rejected =
+        update(&mut chain, contract, ALICE, "reserveTokenRange", &reserve(CHARLIE_ADDR, (50, 150)))
+            .expect_err("Overlapping range");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::TokenRangeOverlap));
+    update(&mut chain, contract, ALICE, "reserveTokenRange", &reserve(CHARLIE_ADDR, (101, 200)))
+        .expect("Reserve range for Charlie");
+    // A new range may overlap the range it replaces.
+    update(&mut chain, contract, ALICE, "reserveTokenRange", &reserve(BOB_ADDR, (1, 50)))
+        .expect("Replace range for Bob");
+
+    mint_as(&mut chain, contract, BOB, BOB, 5).expect("In range");
+    mint_as(&mut chain, contract, CHARLIE, BOB, 150).expect("In range");
+    for token in [60, 150] {
+        let rejected = mint_as(&mut chain, contract, BOB, BOB, token).expect_err("Out of range");
+        let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+        assert_eq!(error, ContractError::Custom(CustomContractError::TokenIdOutOfRange));
+    }
+}
+
+/// Test that `mintNext` assigns sequential token IDs, skipping explicitly
+/// minted ones, and that the counter is kept across an upgrade.
+#[test]
+fn test_mint_next() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 2).expect("Mint token 2 explicitly");
+
+    let params = MintNextParams {
+        owner:  BOB,
+        web3id: "@license".to_string(),
+    };
+    let mint_next = |chain: &mut Chain| -> TokenIdU32 {
+        update(chain, contract, ALICE, "mintNext", &params)
+            .expect("Mint next")
+            .parse_return_value()
+            .expect("Deserialize token ID")
+    };
+    let minted: Vec<TokenIdU32> = (0..3).map(|_| mint_next(&mut chain)).collect();
+    assert_eq!(minted, [TokenIdU32(1), TokenIdU32(3), TokenIdU32(4)]);
+
+    let operation = TimelockOperation::Upgrade(UpgradeParams {
+        module:  module_reference(),
+        migrate: None,
+    });
+    update(&mut chain, contract, ALICE, "queueOperation", &operation).expect("Queue upgrade");
+    chain.tick_block_time(Duration::from_days(2)).expect("Block time does not overflow");
+    update(&mut chain, contract, ALICE, "executeOperation", &operation).expect("Upgrade");
+
+    assert_eq!(mint_next(&mut chain), TokenIdU32(5));
+}
+
+/// Test the metadata URL of token 1 with swapped and with raw token IDs.
+#[test]
+fn test_token_id_url_modes() {
+    let base = "https://licenses.example.com/".to_string();
+    let (chain, contract) = initialize_license_with(InitParams {
+        metadata_base_url: base.clone(),
+        ..Default::default()
+    });
+    let url = preview_metadata_url(&chain, contract, 1, None);
+    assert_eq!(url, "https://licenses.example.com/16777216");
+
+    let (chain, contract) = initialize_license_with(InitParams {
+        metadata_base_url: base,
+        raw_token_id_urls: true,
+        ..Default::default()
+    });
+    let url = preview_metadata_url(&chain, contract, 1, None);
+    assert_eq!(url, "https://licenses.example.com/00000001");
+}
+
+/// Test adding, querying and removing a global operator, which can mint
+/// without the `Minter` role.
+#[test]
+fn test_global_operators() {
+    let (mut chain, contract) = initialize_license();
+    let operators: Vec<Address> = view(&chain, contract, "globalOperators", &());
+    assert!(operators.is_empty());
+
+    update(&mut chain, contract, BOB, "addGlobalOperator", &CHARLIE_ADDR)
+        .expect_err("Only the owner can add global operators");
+    let added = update(&mut chain, contract, ALICE, "addGlobalOperator", &CHARLIE_ADDR)
+        .expect("Add global operator");
+    let events: Vec<ContractEvent> = added
+        .events()
+        .flat_map(|(_, events)| events.iter().map(|e| e.parse().expect("Deserialize event")))
+        .collect();
+    assert_eq!(events, [ContractEvent::Custom(CustomEvent::GlobalOperatorUpdated(
+        GlobalOperatorUpdatedEvent {
+            operator: CHARLIE_ADDR,
+            update:   OperatorUpdate::Add,
+        }
+    ))]);
+    let operators: Vec<Address> = view(&chain, contract, "globalOperators", &());
+    assert_eq!(operators, [CHARLIE_ADDR]);
+    mint_as(&mut chain, contract, CHARLIE, BOB, 1).expect("Global operator mints");
+
+    update(&mut chain, contract, ALICE, "removeGlobalOperator", &CHARLIE_ADDR)
+        .expect("Remove global operator");
+    let operators: Vec<Address> = view(&chain, contract, "globalOperators", &());
+    assert!(operators.is_empty());
+    mint_as(&mut chain, contract, CHARLIE, BOB, 2).expect_err("Removed operator cannot mint");
+}
+
+/// Test that `revokeAndReissue` replaces a license with a fresh token ID held
+/// by the same owner, and rejects unknown licenses.
+#[test]
+fn test_revoke_and_reissue() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    let params = RevokeAndReissueParams {
+        old_token_id: TokenIdU32(1),
+        new_token_id: TokenIdU32(2),
+        web3id:       "@reissued".to_string(),
+    };
+    update(&mut chain, contract, BOB, "revokeAndReissue", &params)
+        .expect_err("Holder cannot reissue");
+    update(&mut chain, contract, ALICE, "revokeAndReissue", &params).expect("Reissue");
+
+    let info: Vec<GateInfo> = view(&chain, contract, "gateInfo", &vec![TokenIdU32(2)]);
+    assert_eq!(info[0].owner, Some(BOB_ADDR));
+    let web3id: Web3Id = view(&chain, contract, "web3IdOf", &TokenIdU32(2));
+    assert_eq!(web3id, "@reissued");
+    update(&mut chain, contract, ALICE, "gateInfo", &vec![TokenIdU32(1)])
+        .expect_err("Old token is burned");
+
+    let missing = update(&mut chain, contract, ALICE, "revokeAndReissue", &params)
+        .expect_err("Old token does not exist");
+    let error: ContractError = missing.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::LicenseNotFound));
+}
+
+/// Test that a license is reissued to a holder at the mint cap and frozen, and
+/// that the replacement keeps the content type, expiry, tier and additional
+/// URLs of the old license, but carries the checksum of the new license as its
+/// hash.
+#[test]
+fn test_revoke_and_reissue_keeps_terms() {
+    let (mut chain, contract) = initialize_license_with(InitParams {
+        per_account_mint_cap: Some(1),
+        ..Default::default()
+    });
+    let expiry = Timestamp::from_timestamp_millis(0).checked_add(Duration::from_days(30)).unwrap();
+    update(&mut chain, contract, ALICE, "mint", &MintParams {
+        owner:        BOB,
+        token:        TokenIdU32(1),
+        web3id:       "@license".to_string(),
+        hash:         Some([1; 32]),
+        content_type: Some("application/json".to_string()),
+        expiry:       Some(expiry),
+        tier:         3,
+    })
+    .expect("Mint token 1");
+    mint(&mut chain, contract, BOB, 5).expect_err("Bob is at the mint cap");
+    let urls = vec![(MetadataRole::Terms, "https://example.com/terms".to_string())];
+    let params = SetAdditionalMetadataUrlsParams {
+        token_id: TokenIdU32(1),
+        urls:     urls.clone(),
+    };
+    update(&mut chain, contract, ALICE, "setAdditionalMetadataUrls", &params)
+        .expect("Set additional URLs");
+    update(&mut chain, contract, ALICE, "setFrozen", &SetFrozenParams {
+        address: BOB_ADDR,
+        frozen:  true,
+    })
+    .expect("Freeze Bob");
+
+    update(&mut chain, contract, ALICE, "revokeAndReissue", &RevokeAndReissueParams {
+        old_token_id: TokenIdU32(1),
+        new_token_id: TokenIdU32(2),
+        web3id:       "@reissued".to_string(),
+    })
+    .expect("Reissue");
+
+    let metadata: Vec<TokenMetadata> =
+        view(&chain, contract, "tokenMetadataExt", &TokenMetadataQueryParams {
+            queries: vec![TokenIdU32(2)],
+        });
+    assert_eq!(metadata[0].content_type.as_deref(), Some("application/json"));
+    assert_eq!(metadata[0].expiry, Some(expiry));
+    assert_eq!(metadata[0].additional_urls, urls);
+    let checksum: [u8; 32] = view(&chain, contract, "computeChecksum", &ComputeChecksumParams {
+        token_id: TokenIdU32(2),
+        web3id:   "@reissued".to_string(),
+    });
+    assert_eq!(metadata[0].hash, Some(checksum));
+    let tiers: Vec<Option<u8>> = view(&chain, contract, "tierOf", &vec![TokenIdU32(2)]);
+    assert_eq!(tiers, [Some(3)]);
+    assert_eq!(view::<_, u32>(&chain, contract, "mintCountOf", &BOB), 1);
+}
+
+/// Test that a burn logs the CIS2 `Burn` event together with a
+/// `LicenseRevoked` event carrying the reason.
+#[test]
+fn test_burn_reason() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    let burned = update(&mut chain, contract, ALICE, "burn", &BurnParams {
+        token_id: TokenIdU32(1),
+        owner:    BOB_ADDR,
+        amount:   TokenAmountU64(1),
+        reason:   BurnReason::Revoked,
+    })
+    .expect("Burner revokes the license");
+    let events: Vec<ContractEvent> = burned
+        .events()
+        .flat_map(|(_, events)| events.iter().map(|e| e.parse().expect("Deserialize event")))
+        .collect();
+    assert_eq!(events, [
+        ContractEvent::Cis2(Cis2Event::Burn(BurnEvent {
+            token_id: TokenIdU32(1),
+            amount:   TokenAmountU64(1),
+            owner:    BOB_ADDR,
+        })),
+        ContractEvent::Custom(CustomEvent::LicenseRevoked(LicenseRevokedEvent {
+            token_id: TokenIdU32(1),
+            owner:    BOB_ADDR,
+            reason:   BurnReason::Revoked,
+        })),
+    ]);
+}
+
+/// Test that transferring to a contract without the CIS2 receive hook rejects
+/// with `ReceiveHookFailed` and leaves the token with its holder.
+#[test]
+fn test_transfer_receive_hook_failed() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    // A second license contract without escrow rejects the tokens.
+    let receiver = chain
+        .contract_init(SIGNER, ALICE, Energy::from(10_000), InitContractPayload {
+            amount:    Amount::zero(),
+            mod_ref:   module_reference(),
+            init_name: OwnedContractName::new_unchecked("init_LicenseContract".to_string()),
+            param:     OwnedParameter::from_serial(&InitParams::default())
+                .expect("Parameter is valid."),
+        })
+        .expect("Initializing receiver contract")
+        .contract_address;
+
+    let rejected = transfer(&mut chain, contract, BOB, BOB_ADDR, Address::Contract(receiver), 1)
+        .expect_err("Receive hook is missing");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::ReceiveHookFailed));
+    assert_eq!(balance_of(&chain, contract, BOB_ADDR, 1), TokenAmountU64(1));
+}
+
+/// Test that `view` returns tokens and operators sorted, regardless of the
+/// order they were added in.
+#[test]
+fn test_view_sorted() {
+    let (mut chain, contract) = initialize_license();
+    for token in [300, 2, 70_000, 1, 256] {
+        mint(&mut chain, contract, BOB, token).expect("Mint token");
+    }
+    for operator in [CHARLIE_ADDR, BOB_ADDR] {
+        update(&mut chain, contract, ALICE, "addGlobalOperator", &operator)
+            .expect("Add global operator");
+    }
+
+    let state: ViewState = view(&chain, contract, "view", &());
+    let sorted: Vec<TokenIdU32> = [1, 2, 256, 300, 70_000].into_iter().map(TokenIdU32).collect();
+    assert_eq!(state.all_tokens, sorted);
+    assert_eq!(state.operators, [BOB_ADDR, CHARLIE_ADDR]);
+    let (_, bob) =
+        state.state.iter().find(|(address, _)| *address == BOB_ADDR).expect("Bob has a state");
+    assert_eq!(bob.owned_tokens, sorted);
+}
+
+/// Test that a `mintBatch` with a token ID repeated within the batch, or one
+/// which already exists, mints none of the tokens.
+#[test]
+fn test_mint_batch_duplicates() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 10).expect("Mint token 10");
+
+    for ids in [[1, 2, 1], [1, 2, 10]] {
+        let tokens = ids.iter().map(|id| (TokenIdU32(*id), "@license".to_string())).collect();
+        let rejected = update(&mut chain, contract, ALICE, "mintBatch", &MintBatchParams {
+            owner: BOB,
+            tokens,
+        })
+        .expect_err("Batch contains a duplicate");
+        let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+        assert_eq!(error, ContractError::Custom(CustomContractError::TokenIdAlreadyExists));
+    }
+
+    let state: ViewState = view(&chain, contract, "view", &());
+    assert_eq!(state.all_tokens, [TokenIdU32(10)]);
+}
+
+/// Test that `isValidBatch` tells apart valid, expired, unknown and revoked
+/// licenses in one call.
+#[test]
+fn test_is_valid_batch() {
+    let (mut chain, contract) = initialize_license();
+    let expiry = Timestamp::from_timestamp_millis(0).checked_add(Duration::from_days(1)).unwrap();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    mint_with_expiry(&mut chain, contract, BOB, 2, expiry).expect("Mint expiring token 2");
+    mint(&mut chain, contract, BOB, 4).expect("Mint token 4");
+    update(&mut chain, contract, ALICE, "burn", &BurnParams {
+        token_id: TokenIdU32(4),
+        owner:    BOB_ADDR,
+        amount:   TokenAmountU64(1),
+        reason:   BurnReason::Revoked,
+    })
+    .expect("Revoke token 4");
+    chain.tick_block_time(Duration::from_days(2)).expect("Block time does not overflow");
+
+    let tokens: Vec<TokenIdU32> = (1..=4).map(TokenIdU32).collect();
+    let statuses: Vec<ValidityStatus> = view(&chain, contract, "isValidBatch", &tokens);
+    assert_eq!(statuses, [
+        ValidityStatus::Valid,
+        ValidityStatus::Expired,
+        ValidityStatus::Nonexistent,
+        ValidityStatus::Revoked,
+    ]);
+}
+
+/// Test `verifyMetadataHash` with a matching and a non-matching hash, and an
+/// unknown token.
+#[test]
+fn test_verify_metadata_hash() {
+    let (mut chain, contract) = initialize_license();
+    let hash = [7u8; 32];
+    update(&mut chain, contract, ALICE, "mint", &MintParams {
+        owner:        BOB,
+        token:        TokenIdU32(1),
+        web3id:       "@license".to_string(),
+        hash:         Some(hash),
+        content_type: None,
+        expiry:       None,
+        tier:         0,
+    })
+    .expect("Mint with hash");
+
+    let verify = |expected: [u8; 32]| VerifyMetadataHashParams {
+        token_id: TokenIdU32(1),
+        expected,
+    };
+    assert!(view::<_, bool>(&chain, contract, "verifyMetadataHash", &verify(hash)));
+    assert!(!view::<_, bool>(&chain, contract, "verifyMetadataHash", &verify([8u8; 32])));
+
+    let unknown = VerifyMetadataHashParams {
+        token_id: TokenIdU32(2),
+        expected: hash,
+    };
+    let rejected = update(&mut chain, contract, ALICE, "verifyMetadataHash", &unknown)
+        .expect_err("Unknown token");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::InvalidTokenId);
+}
+
+/// Test that a frozen address can neither send nor receive tokens, while
+/// transfers between other addresses proceed.
+#[test]
+fn test_frozen_address() {
+    let (mut chain, contract) = initialize_license();
+    for token in 1..=3 {
+        mint(&mut chain, contract, BOB, token).expect("Mint token");
+    }
+    mint(&mut chain, contract, CHARLIE, 4).expect("Mint token 4");
+
+    let freeze = |address: Address, frozen: bool| SetFrozenParams {
+        address,
+        frozen,
+    };
+    update(&mut chain, contract, BOB, "setFrozen", &freeze(CHARLIE_ADDR, true))
+        .expect_err("Only admins can freeze");
+    update(&mut chain, contract, ALICE, "setFrozen", &freeze(CHARLIE_ADDR, true))
+        .expect("Freeze Charlie");
+    assert!(view::<_, bool>(&chain, contract, "isFrozen", &CHARLIE_ADDR));
+    assert!(!view::<_, bool>(&chain, contract, "isFrozen", &BOB_ADDR));
+
+    let assert_frozen = |result: Result<ContractInvokeSuccess, ContractInvokeError>| {
+        let error: ContractError =
+            result.expect_err("Address is frozen").parse_return_value().expect("Deserialize error");
+        assert_eq!(error, ContractError::Custom(CustomContractError::AddressFrozen));
+    };
+    assert_frozen(transfer(&mut chain, contract, CHARLIE, CHARLIE_ADDR, BOB_ADDR, 4));
+    assert_frozen(transfer(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 1));
+    assert_frozen(mint(&mut chain, contract, CHARLIE, 5));
+    transfer(&mut chain, contract, BOB, BOB_ADDR, DAVE_ADDR, 2).expect("Unrelated transfer");
+
+    update(&mut chain, contract, ALICE, "setFrozen", &freeze(CHARLIE_ADDR, false))
+        .expect("Unfreeze Charlie");
+    transfer(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 3).expect("Charlie receives");
+}
+
+/// Test that a migration invoked by an upgrade reads the old state version
+/// and writes the version of the new module, and that it cannot be invoked
+/// directly.
+#[test]
+fn test_upgrade_state_version() {
+    let (mut chain, contract) = initialize_license();
+    let version: u16 = view(&chain, contract, "stateVersion", &());
+    let module_version: u16 = view(&chain, contract, "moduleStateVersion", &());
+    assert_eq!(version, module_version);
+
+    update(&mut chain, contract, ALICE, "migrate", &()).expect_err("Only the contract migrates");
+
+    let operation = TimelockOperation::Upgrade(UpgradeParams {
+        module:  module_reference(),
+        migrate: Some((
+            OwnedEntrypointName::new_unchecked("migrate".to_string()),
+            OwnedParameter::empty(),
+        )),
+    });
+    update(&mut chain, contract, ALICE, "queueOperation", &operation).expect("Queue upgrade");
+    chain.tick_block_time(Duration::from_days(2)).expect("Block time does not overflow");
+    update(&mut chain, contract, ALICE, "executeOperation", &operation)
+        .expect("Upgrade with migration");
+
+    let version: u16 = view(&chain, contract, "stateVersion", &());
+    assert_eq!(version, module_version);
+}
+
+/// Test that an `updateOperator` batch exceeding the log limit rejects with
+/// `LogFull` without adding any operator.
+#[test]
+fn test_update_operator_log_limit() {
+    let (mut chain, contract) = initialize_license();
+    let updates = |count: u64| {
+        UpdateOperatorParams(
+            (0..count)
+                .map(|index| UpdateOperator {
+                    update:   OperatorUpdate::Add,
+                    operator: Address::Contract(ContractAddress::new(100 + index, 0)),
+                })
+                .collect(),
+        )
+    };
+
+    let rejected = update(&mut chain, contract, BOB, "updateOperator", &updates(33))
+        .expect_err("Batch exceeds the log limit");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::LogFull));
+    let graph: OperatorGraph = view(&chain, contract, "operatorGraph", &BOB_ADDR);
+    assert!(graph.operators.is_empty());
+
+    update(&mut chain, contract, BOB, "updateOperator", &updates(32))
+        .expect("Batch within the log limit");
+    let graph: OperatorGraph = view(&chain, contract, "operatorGraph", &BOB_ADDR);
+    assert_eq!(graph.operators.len(), 32);
+}
+
+/// Test that a `tokenMetadata` batch of 100 queries returns exactly the
+/// metadata of the individual queries, in order.
+#[test]
+fn test_token_metadata_batch() {
+    let (mut chain, contract) = initialize_license_with(InitParams {
+        metadata_base_url: "https://licenses.example.com/".to_string(),
+        ..Default::default()
+    });
+    for token in 1..=10 {
+        mint(&mut chain, contract, BOB, token).expect("Mint token");
+    }
+    update(&mut chain, contract, ALICE, "mintFungible", &MintFungibleParams {
+        owner:  BOB,
+        token:  TokenIdU32(11),
+        amount: TokenAmountU64(5),
+    })
+    .expect("Mint fungible pool");
+
+    let tokens: Vec<u32> = (0..100).map(|i| i % 11 + 1).collect();
+    let response: TokenMetadataQueryResponse =
+        view(&chain, contract, "tokenMetadata", &TokenMetadataQueryParams {
+            queries: tokens.iter().map(|token| TokenIdU32(*token)).collect(),
+        });
+    assert_eq!(response.0.len(), 100);
+    for (metadata, token) in response.0.iter().zip(tokens) {
+        let single = token_metadata(&chain, contract, token);
+        assert_eq!((&metadata.url, metadata.hash), (&single.url, single.hash));
+    }
+}
+
+/// Test that adding an address as its own operator rejects with
+/// `SelfOperator`.
+#[test]
+fn test_self_operator() {
+    let (mut chain, contract) = initialize_license();
+    let rejected = add_operator(&mut chain, contract, BOB, BOB_ADDR).expect_err("Self operator");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::SelfOperator));
+
+    let graph: OperatorGraph = view(&chain, contract, "operatorGraph", &BOB_ADDR);
+    assert!(graph.operators.is_empty());
+}
+
+/// Test minting a token with an `ipfs://` metadata URL which is returned as
+/// is by `tokenMetadata`, and that malformed URLs are rejected.
+#[test]
+fn test_mint_with_metadata() {
+    let (mut chain, contract) = initialize_license_with(InitParams {
+        metadata_base_url: "https://licenses.example.com/".to_string(),
+        ..Default::default()
+    });
+    let params = |token: u32, url: &str| MintWithMetadataParams {
+        owner: BOB,
+        token: TokenIdU32(token),
+        url:   url.to_string(),
+        hash:  Some([7u8; 32]),
+    };
+    let url = "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi";
+
+    update(&mut chain, contract, BOB, "mintWithMetadata", &params(1, url))
+        .expect_err("Non-minter cannot mint");
+    update(&mut chain, contract, ALICE, "mintWithMetadata", &params(1, url))
+        .expect("Mint with custom URL");
+    let metadata = token_metadata(&chain, contract, 1);
+    assert_eq!(metadata.url, url);
+    assert_eq!(metadata.hash, Some([7u8; 32]));
+
+    for invalid in ["", "no-scheme", "ipfs://with space", "1ipfs://cid", "ipfs:"] {
+        let rejected = update(&mut chain, contract, ALICE, "mintWithMetadata", &params(2, invalid))
+            .expect_err("Malformed URL");
+        let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+        assert_eq!(error, ContractError::Custom(CustomContractError::InvalidMetadataUrl));
+    }
+}
+
+/// Test that `mint` and `mintBatch` return the IDs of the minted tokens.
+#[test]
+fn test_mint_returns_token_id() {
+    let (mut chain, contract) = initialize_license();
+    let minted: TokenIdU32 = mint(&mut chain, contract, BOB, 7)
+        .expect("Mint token 7")
+        .parse_return_value()
+        .expect("Deserialize token ID");
+    assert_eq!(minted, TokenIdU32(7));
+
+    let params = MintBatchParams {
+        owner:  BOB,
+        tokens: vec![
+            (TokenIdU32(3), "@license".to_string()),
+            (TokenIdU32(1), "@other".to_string()),
+        ],
+    };
+    let batch: Vec<TokenIdU32> = update(&mut chain, contract, ALICE, "mintBatch", &params)
+        .expect("Mint batch")
+        .parse_return_value()
+        .expect("Deserialize token IDs");
+    assert_eq!(batch, [TokenIdU32(3), TokenIdU32(1)]);
+
+    let state: ViewState = view(&chain, contract, "view", &());
+    assert_eq!(state.all_tokens, [TokenIdU32(1), TokenIdU32(3), minted]);
+}
+
+/// Test that a burn approval lets the spender burn the token exactly once,
+/// and that unapproved burns reject.
+#[test]
+fn test_burn_from() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    mint(&mut chain, contract, BOB, 2).expect("Mint token 2");
+
+    let burn_from = |token: u32| BurnFromParams {
+        token_id: TokenIdU32(token),
+        owner:    BOB_ADDR,
+        reason:   BurnReason::OwnerRequested,
+    };
+    let approve = ApproveBurnParams {
+        token_id: TokenIdU32(1),
+        spender:  CHARLIE_ADDR,
+    };
+    update(&mut chain, contract, CHARLIE, "approveBurn", &approve)
+        .expect_err("Only the holder can approve");
+    update(&mut chain, contract, BOB, "approveBurn", &approve).expect("Approve burn");
+
+    let unapproved = update(&mut chain, contract, CHARLIE, "burnFrom", &burn_from(2))
+        .expect_err("Token 2 is not approved");
+    let error: ContractError = unapproved.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Unauthorized);
+
+    update(&mut chain, contract, CHARLIE, "burnFrom", &burn_from(1)).expect("Burn approved");
+    assert_eq!(view::<_, u64>(&chain, contract, "totalSupply", &()), 1);
+
+    // The approval is consumed, even if the token ID is minted again.
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1 again");
+    let reused = update(&mut chain, contract, CHARLIE, "burnFrom", &burn_from(1))
+        .expect_err("Approval is single-use");
+    let error: ContractError = reused.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Unauthorized);
+}
+
+/// Test that after a sequence of random transfers the holder of each token
+/// from `tokenOwner` matches the owned tokens of the addresses in `view`.
+#[test]
+fn test_token_owner_index() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let (mut chain, contract) = initialize_license();
+    let holders = [(BOB, BOB_ADDR), (CHARLIE, CHARLIE_ADDR), (ALICE, ALICE_ADDR)];
+    for token in 1..=10 {
+        mint(&mut chain, contract, BOB, token).expect("Mint token");
+    }
+
+    let mut rng = StdRng::seed_from_u64(42);
+    for _ in 0..50 {
+        let token = rng.gen_range(1..=10);
+        let (_, to) = holders[rng.gen_range(0..holders.len())];
+        let owners: Vec<Option<Address>> =
+            view(&chain, contract, "tokenOwner", &TokenOwnerQueryParams {
+                queries: vec![TokenIdU32(token)],
+            });
+        let from = owners[0].expect("Token has a holder");
+        let (sender, _) = holders.iter().find(|(_, address)| *address == from).unwrap();
+        transfer(&mut chain, contract, *sender, from, to, token).expect("Transfer");
+    }
+
+    let tokens: Vec<TokenIdU32> = (1..=10).map(TokenIdU32).collect();
+    let owners: Vec<Option<Address>> = view(&chain, contract, "tokenOwner", &TokenOwnerQueryParams {
+        queries: tokens.clone(),
+    });
+    let state: ViewState = view(&chain, contract, "view", &());
+    for (token, owner) in tokens.iter().zip(owners) {
+        let holders: Vec<Address> = state
+            .state
+            .iter()
+            .filter(|(_, address_state)| address_state.owned_tokens.contains(token))
+            .map(|(address, _)| *address)
+            .collect();
+        assert_eq!(holders, owner.into_iter().collect::<Vec<_>>());
+        assert_eq!(balance_of(&chain, contract, owner.unwrap(), token.0), TokenAmountU64(1));
+    }
+}
+
+/// Test updating the expiry of 20 licenses with `setExpiryBatch`, and that a
+/// batch with a missing license updates none of them.
+#[test]
+fn test_set_expiry_batch() {
+    let (mut chain, contract) = initialize_license();
+    for token in 1..=20 {
+        mint(&mut chain, contract, BOB, token).expect("Mint token");
+    }
+    let expiry = |token: u32| Timestamp::from_timestamp_millis(1_000 * u64::from(token));
+    let updates: Vec<(TokenIdU32, Timestamp)> =
+        (1..=20).map(|token| (TokenIdU32(token), expiry(token))).collect();
+
+    update(&mut chain, contract, BOB, "setExpiryBatch", &updates).expect_err("Bob has no role");
+    update(&mut chain, contract, ALICE, "grantRole", &RoleParams {
+        address: CHARLIE_ADDR,
+        role:    Role::Minter,
+    })
+    .expect("Grant minter role");
+    let updated =
+        update(&mut chain, contract, CHARLIE, "setExpiryBatch", &updates).expect("Set expiries");
+
+    let events: Vec<CustomEvent> = updated
+        .events()
+        .flat_map(|(_, events)| events.iter().map(|e| e.parse().expect("Deserialize event")))
+        .collect();
+    let expected: Vec<CustomEvent> = updates
+        .iter()
+        .map(|(token_id, new_expiry)| {
+            CustomEvent::LicenseRenewed(LicenseRenewedEvent {
+                token_id:   *token_id,
+                new_expiry: *new_expiry,
+            })
+        })
+        .collect();
+    assert_eq!(events, expected);
+    let tokens: Vec<TokenIdU32> = (1..=20).map(TokenIdU32).collect();
+    let info: Vec<GateInfo> = view(&chain, contract, "gateInfo", &tokens);
+    for (token, info) in (1..=20).zip(info) {
+        assert_eq!(info.expiry, Some(expiry(token)));
+    }
+
+    let missing = vec![(TokenIdU32(1), expiry(99)), (TokenIdU32(21), expiry(99))];
+    let rejected = update(&mut chain, contract, ALICE, "setExpiryBatch", &missing)
+        .expect_err("Token 21 does not exist");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::LicenseNotFound));
+    let info: Vec<GateInfo> = view(&chain, contract, "gateInfo", &vec![TokenIdU32(1)]);
+    assert_eq!(info[0].expiry, Some(expiry(1)));
+}
+
+/// Test that a contract only becomes the owner when explicitly allowed.
+#[test]
+fn test_transfer_ownership_to_contract() {
+    let (mut chain, contract) = initialize_license();
+    let params = |allow_contract_owner: bool| TransferOwnershipParams {
+        new_owner: Address::Contract(ContractAddress::new(42, 0)),
+        allow_contract_owner,
+    };
+
+    let rejected = update(&mut chain, contract, ALICE, "transferOwnership", &params(false))
+        .expect_err("Contract owner not allowed");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::InvalidOwner));
+
+    update(&mut chain, contract, ALICE, "transferOwnership", &params(true))
+        .expect("Contract owner explicitly allowed");
+    update(&mut chain, contract, ALICE, "setContractInfo", &ContractInfo::default())
+        .expect_err("Previous owner is no longer the owner");
+}
+
+/// Test that a transfer of amount zero succeeds without logging an event.
+#[test]
+fn test_transfer_zero_amount() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    let transferred = transfer_amount(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 1, 0)
+        .expect("Zero transfer succeeds");
+    assert_eq!(transferred.events().flat_map(|(_, events)| events.iter()).count(), 0);
+    assert_eq!(balance_of(&chain, contract, BOB_ADDR, 1), TokenAmountU64(1));
+}
+
+/// Test that the checksum is deterministic, differs between web3ids and is
+/// stored and logged as the metadata hash at mint.
+#[test]
+fn test_compute_checksum() {
+    let (mut chain, contract) = initialize_license();
+    let checksum = |chain: &Chain, token: u32, web3id: &str| -> [u8; 32] {
+        view(chain, contract, "computeChecksum", &ComputeChecksumParams {
+            token_id: TokenIdU32(token),
+            web3id:   web3id.to_string(),
+        })
+    };
+    assert_eq!(checksum(&chain, 1, "@license"), checksum(&chain, 1, "@license"));
+    assert_ne!(checksum(&chain, 1, "@license"), checksum(&chain, 1, "@licensf"));
+    assert_ne!(checksum(&chain, 1, "@license"), checksum(&chain, 2, "@license"));
+
+    let minted = mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    let logged_hash = minted
+        .events()
+        .flat_map(|(_, events)| events.iter())
+        .find_map(|event| match event.parse::<Cis2Event<TokenIdU32, TokenAmountU64>>() {
+            Ok(Cis2Event::TokenMetadata(metadata)) => metadata.metadata_url.hash,
+            _ => None,
+        })
+        .expect("TokenMetadata event logged with hash");
+    assert_eq!(logged_hash, checksum(&chain, 1, "@license"));
+}
+
+/// Test that an operator scoped to a single token can transfer that token but
+/// no other token of the owner.
+#[test]
+fn test_scoped_operator() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    mint(&mut chain, contract, BOB, 2).expect("Mint token 2");
+
+    let scoped = |update| UpdateScopedOperatorParams {
+        update,
+        operator: CHARLIE_ADDR,
+        token_id: TokenIdU32(1),
+    };
+    update(&mut chain, contract, BOB, "updateScopedOperator", &scoped(OperatorUpdate::Add))
+        .expect("Add scoped operator");
+
+    let graph: OperatorGraph = view(&chain, contract, "operatorGraph", &BOB_ADDR);
+    assert_eq!(graph, OperatorGraph {
+        operators: vec![],
+        scoped:    vec![(CHARLIE_ADDR, vec![TokenIdU32(1)])],
+    });
+
+    let rejected = transfer(&mut chain, contract, CHARLIE, BOB_ADDR, CHARLIE_ADDR, 2)
+        .expect_err("Transfer of a different token");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Unauthorized);
+
+    transfer(&mut chain, contract, CHARLIE, BOB_ADDR, ALICE_ADDR, 1)
+        .expect("Transfer of the approved token");
+    assert_eq!(balance_of(&chain, contract, ALICE_ADDR, 1), TokenAmountU64(1));
+
+    // Removing the scope revokes the permission again.
+    update(&mut chain, contract, BOB, "updateScopedOperator", &scoped(OperatorUpdate::Add))
+        .expect("Add scoped operator");
+    update(&mut chain, contract, BOB, "updateScopedOperator", &scoped(OperatorUpdate::Remove))
+        .expect("Remove scoped operator");
+    let graph: OperatorGraph = view(&chain, contract, "operatorGraph", &BOB_ADDR);
+    assert!(graph.scoped.is_empty());
+
+    // Global operators keep working unchanged.
+    add_operator(&mut chain, contract, BOB, CHARLIE_ADDR).expect("Add Charlie");
+    transfer(&mut chain, contract, CHARLIE, BOB_ADDR, CHARLIE_ADDR, 2)
+        .expect("Transfer as global operator");
+}
+
+/// Test that a scoped approval ends when the token leaves the owner and does
+/// not come back with the token.
+#[test]
+fn test_scoped_operator_cleared_on_transfer() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    update(&mut chain, contract, BOB, "updateScopedOperator", &UpdateScopedOperatorParams {
+        update:   OperatorUpdate::Add,
+        operator: CHARLIE_ADDR,
+        token_id: TokenIdU32(1),
+    })
+    .expect("Add scoped operator");
+
+    transfer(&mut chain, contract, BOB, BOB_ADDR, ALICE_ADDR, 1).expect("Transfer to Alice");
+    transfer(&mut chain, contract, ALICE, ALICE_ADDR, BOB_ADDR, 1).expect("Transfer back to Bob");
+
+    let graph: OperatorGraph = view(&chain, contract, "operatorGraph", &BOB_ADDR);
+    assert!(graph.scoped.is_empty());
+    let rejected = transfer(&mut chain, contract, CHARLIE, BOB_ADDR, CHARLIE_ADDR, 1)
+        .expect_err("The approval ended with the first transfer");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Unauthorized);
+}
+
+/// Test that a contract with escrow enabled records the NFTs it receives and
+/// releases them only to the address which sent them.
+#[test]
+fn test_escrow() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    let escrow = chain
+        .contract_init(SIGNER, ALICE, Energy::from(10_000), InitContractPayload {
+            amount:    Amount::zero(),
+            mod_ref:   module_reference(),
+            init_name: OwnedContractName::new_unchecked("init_LicenseContract".to_string()),
+            param:     OwnedParameter::from_serial(&InitParams {
+                escrow_enabled: true,
+                ..Default::default()
+            })
+            .expect("Parameter is valid."),
+        })
+        .expect("Initializing escrow contract")
+        .contract_address;
+
+    transfer(&mut chain, contract, BOB, BOB_ADDR, Address::Contract(escrow), 1)
+        .expect("Transfer into escrow");
+    assert_eq!(balance_of(&chain, contract, Address::Contract(escrow), 1), TokenAmountU64(1));
+    let holder: Option<Address> =
+        view(&chain, escrow, "escrowHolder", &(contract, escrowed_id(1)));
+    assert_eq!(holder, Some(BOB_ADDR));
+
+    let withdraw = WithdrawEscrowParams {
+        contract,
+        token_id: escrowed_id(1),
+        to: Receiver::Account(CHARLIE),
+    };
+    let rejected = update(&mut chain, escrow, CHARLIE, "withdrawEscrow", &withdraw)
+        .expect_err("Only the depositor withdraws");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Unauthorized);
+
+    update(&mut chain, escrow, BOB, "withdrawEscrow", &withdraw).expect("Depositor withdraws");
+    assert_eq!(balance_of(&chain, contract, CHARLIE_ADDR, 1), TokenAmountU64(1));
+    let holder: Option<Address> =
+        view(&chain, escrow, "escrowHolder", &(contract, escrowed_id(1)));
+    assert_eq!(holder, None);
+}
+
+/// The raw form of a token ID of this contract, as held in escrow by another.
+fn escrowed_id(token_id: u32) -> TokenIdVec { TokenIdVec(token_id.to_le_bytes().to_vec()) }
+
+/// Test that the owner can distribute licenses to several recipients at once
+/// and that no one else can.
+#[test]
+fn test_distribute() {
+    let (mut chain, contract) = initialize_license();
+    let recipients = [ALICE, BOB, CHARLIE, DAVE, AccountAddress([4u8; 32])];
+    let params: Vec<(AccountAddress, TokenIdU32, String)> = recipients
+        .iter()
+        .enumerate()
+        .map(|(i, account)| (*account, TokenIdU32(i as u32 + 1), "@license".to_string()))
+        .collect();
+
+    let rejected =
+        update(&mut chain, contract, BOB, "distribute", &params).expect_err("Only the owner");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Unauthorized);
+
+    let distributed =
+        update(&mut chain, contract, ALICE, "distribute", &params).expect("Owner distributes");
+    let minted: Vec<TokenIdU32> =
+        distributed.parse_return_value().expect("Deserialize token IDs");
+    assert_eq!(minted, (1..=5).map(TokenIdU32).collect::<Vec<_>>());
+    for (i, account) in recipients.iter().enumerate() {
+        let balance = balance_of(&chain, contract, Address::Account(*account), i as u32 + 1);
+        assert_eq!(balance, TokenAmountU64(1));
+    }
+}
+
+/// Test that tokens cannot be transferred to the blackhole address to fake a
+/// burn.
+#[test]
+fn test_transfer_to_blackhole() {
+    let blackhole = Address::Account(AccountAddress([0xffu8; 32]));
+    let (mut chain, contract) = initialize_license_with(InitParams {
+        blackhole: Some(blackhole),
+        ..Default::default()
+    });
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    let rejected = transfer(&mut chain, contract, BOB, BOB_ADDR, blackhole, 1)
+        .expect_err("Transfer to the blackhole");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::UseBurnInstead));
+    assert_eq!(balance_of(&chain, contract, BOB_ADDR, 1), TokenAmountU64(1));
+
+    transfer(&mut chain, contract, BOB, BOB_ADDR, CHARLIE_ADDR, 1).expect("Regular transfer");
+}
+
+/// Test that `pausedTokens` returns the paused tokens sorted and paginated.
+#[test]
+fn test_paused_tokens() {
+    let (mut chain, contract) = initialize_license();
+    for token in [300, 2, 70_000, 1, 256] {
+        mint(&mut chain, contract, BOB, token).expect("Mint token");
+    }
+    for token in [70_000, 2, 300, 1] {
+        update(&mut chain, contract, ALICE, "setPaused", &SetPausedParams {
+            token_id: TokenIdU32(token),
+            paused:   true,
+        })
+        .expect("Pause token");
+    }
+
+    let page = |chain: &Chain, skip: u32, take: u32| -> Vec<TokenIdU32> {
+        view(chain, contract, "pausedTokens", &PausedTokensParams {
+            skip,
+            take,
+        })
+    };
+    let all: Vec<TokenIdU32> = [1, 2, 300, 70_000].into_iter().map(TokenIdU32).collect();
+    assert_eq!(page(&chain, 0, 10), all);
+    assert_eq!(page(&chain, 0, 2), vec![TokenIdU32(1), TokenIdU32(2)]);
+    assert_eq!(page(&chain, 2, 2), vec![TokenIdU32(300), TokenIdU32(70_000)]);
+    assert!(page(&chain, 4, 2).is_empty());
+}
+
+/// Test that additional metadata URLs round-trip through
+/// `tokenMetadataExtended` while `tokenMetadata` keeps the primary URL.
+#[test]
+fn test_additional_metadata_urls() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    let primary = token_metadata(&chain, contract, 1).url;
+
+    let urls = vec![
+        (MetadataRole::Document, "https://example.com/license.pdf".to_string()),
+        (MetadataRole::Thumbnail, "https://example.com/license.png".to_string()),
+        (MetadataRole::Terms, "https://example.com/terms".to_string()),
+    ];
+    let params = SetAdditionalMetadataUrlsParams {
+        token_id: TokenIdU32(1),
+        urls:     urls.clone(),
+    };
+    update(&mut chain, contract, BOB, "setAdditionalMetadataUrls", &params)
+        .expect_err("Only the owner or an admin");
+    update(&mut chain, contract, ALICE, "setAdditionalMetadataUrls", &params)
+        .expect("Set additional URLs");
+
+    let extended: Vec<TokenMetadataExtended> =
+        view(&chain, contract, "tokenMetadataExtended", &TokenMetadataQueryParams {
+            queries: vec![TokenIdU32(1)],
+        });
+    assert_eq!(extended, vec![TokenMetadataExtended {
+        url:             primary.clone(),
+        additional_urls: urls,
+    }]);
+    assert_eq!(token_metadata(&chain, contract, 1).url, primary);
+}
+
+/// Test that burning reports a token which was never minted and a token
+/// which the owner does not hold with different errors.
+#[test]
+fn test_burn_errors() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, CHARLIE, 1).expect("Mint token 1");
+
+    let rejected = burn(&mut chain, contract, BOB, BOB_ADDR, 2).expect_err("Never minted");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::InvalidTokenId);
+
+    // Bob has no entry in the state.
+    let rejected = burn(&mut chain, contract, BOB, BOB_ADDR, 1).expect_err("Not held by Bob");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::NotTokenOwner));
+
+    // Bob has an entry in the state, but does not hold token 1.
+    mint(&mut chain, contract, BOB, 2).expect("Mint token 2");
+    let rejected = burn(&mut chain, contract, BOB, BOB_ADDR, 1).expect_err("Not held by Bob");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::NotTokenOwner));
+    assert_eq!(balance_of(&chain, contract, CHARLIE_ADDR, 1), TokenAmountU64(1));
+}
+
+/// Test that the collection metadata URL is set at init and can be updated by
+/// the owner only.
+#[test]
+fn test_contract_metadata() {
+    let (mut chain, contract) = initialize_license_with(InitParams {
+        collection_metadata_url: "https://licenses.example.com/collection.json".to_string(),
+        ..Default::default()
+    });
+    let metadata: MetadataUrl = view(&chain, contract, "contractMetadata", &());
+    assert_eq!(metadata.url, "https://licenses.example.com/collection.json");
+    assert_eq!(metadata.hash, None);
+
+    let updated = MetadataUrl {
+        url:  "https://licenses.example.com/v2/collection.json".to_string(),
+        hash: Some([1u8; 32]),
+    };
+    let rejected = update(&mut chain, contract, BOB, "setContractMetadata", &updated)
+        .expect_err("Only the owner");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Unauthorized);
+    update(&mut chain, contract, ALICE, "setContractMetadata", &updated).expect("Owner updates");
+
+    let metadata: MetadataUrl = view(&chain, contract, "contractMetadata", &());
+    assert_eq!(metadata, updated);
+}
+
+/// Test that every custom error keeps its fixed reject code.
+#[test]
+fn test_reject_codes() {
+    let codes = [
+        (CustomContractError::ParseParams, -1),
+        (CustomContractError::LogFull, -2),
+        (CustomContractError::LogMalformed, -3),
+        (CustomContractError::TokenIdAlreadyExists, -4),
+        (CustomContractError::InvokeContractError, -5),
+        (CustomContractError::InvalidWeb3Id, -6),
+        (CustomContractError::LicenseNotFound, -7),
+        (CustomContractError::Unauthorized, -8),
+        (CustomContractError::ContractInfoTooLong, -9),
+        (CustomContractError::SupplyOverflow, -10),
+        (CustomContractError::OperationPaused, -11),
+        (CustomContractError::ContractPaused, -12),
+        (CustomContractError::TokenPaused, -13),
+        (CustomContractError::ContractHoldersForbidden, -14),
+        (CustomContractError::RecipientNotRegistered, -15),
+        (CustomContractError::OperationAlreadyQueued, -16),
+        (CustomContractError::OperationNotQueued, -17),
+        (CustomContractError::TimelockNotElapsed, -18),
+        (CustomContractError::LockupActive, -19),
+        (CustomContractError::NonceMismatch, -20),
+        (CustomContractError::WrongContract, -21),
+        (CustomContractError::Expired, -22),
+        (CustomContractError::WrongSignature, -23),
+        (CustomContractError::WrongEntryPoint, -24),
+        (CustomContractError::MissingAccount, -25),
+        (CustomContractError::MalformedData, -26),
+        (CustomContractError::InvalidRoyalty, -27),
+        (CustomContractError::TokenIdOutOfRange, -28),
+        (CustomContractError::InvalidTokenRange, -29),
+        (CustomContractError::TokenRangeOverlap, -30),
+        (CustomContractError::ReceiveHookFailed, -31),
+        (CustomContractError::AddressFrozen, -32),
+        (CustomContractError::MigrationRequired, -33),
+        (CustomContractError::StateVersionMismatch, -34),
+        (CustomContractError::SelfOperator, -35),
+        (CustomContractError::InvalidMetadataUrl, -36),
+        (CustomContractError::InvalidOwner, -37),
+        (CustomContractError::EscrowRejected, -38),
+        (CustomContractError::NotEscrowed, -39),
+        (CustomContractError::UseBurnInstead, -40),
+        (CustomContractError::NotTokenOwner, -41),
+        (CustomContractError::MintQuotaExceeded, -42),
+        (CustomContractError::RenounceNotConfirmed, -43),
+        (CustomContractError::TokenIdSpaceExhausted, -44),
+        (CustomContractError::InsufficientBalance, -45),
+        (CustomContractError::CallbackFailed, -46),
+    ];
+    for (error, code) in codes {
+        assert_eq!(error.reject_code(), code, "Reject code of {:?}", error);
+    }
+}
+
+/// Test that the contract rejects with the baseline code of `InvalidWeb3Id`.
+#[test]
+fn test_invalid_web3id_reject_code() {
+    let (mut chain, contract) = initialize_license();
+    let rejected = update(&mut chain, contract, ALICE, "mint", &MintParams {
+        owner:        BOB,
+        token:        TokenIdU32(1),
+        web3id:       "aesirx".to_string(),
+        hash:         None,
+        content_type: None,
+        expiry:       None,
+        tier:         0,
+    })
+    .expect_err("Malformed web3id");
+    assert!(matches!(rejected.kind, ContractInvokeErrorKind::ExecutionError {
+        failure_kind: InvokeFailure::ContractReject { code: -6, .. },
+    }));
+}
+
+/// Test that an account can be minted licenses up to the mint cap, but no
+/// more, and that the count includes burned licenses.
+#[test]
+fn test_mint_cap() {
+    let (mut chain, contract) = initialize_license_with(InitParams {
+        per_account_mint_cap: Some(2),
+        ..Default::default()
+    });
+    mint(&mut chain, contract, BOB, 1).expect("First mint");
+    burn(&mut chain, contract, BOB, BOB_ADDR, 1).expect("Burn token 1");
+    mint(&mut chain, contract, BOB, 2).expect("Second mint");
+    assert_eq!(view::<_, u32>(&chain, contract, "mintCountOf", &BOB), 2);
+
+    let rejected = mint(&mut chain, contract, BOB, 3).expect_err("Over the cap");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::MintQuotaExceeded));
+    assert_eq!(view::<_, u32>(&chain, contract, "mintCountOf", &BOB), 2);
+
+    // The cap applies per account.
+    mint(&mut chain, contract, CHARLIE, 3).expect("Mint to another account");
+    assert_eq!(view::<_, u32>(&chain, contract, "mintCountOf", &CHARLIE), 1);
+}
+
+/// Test that minting is unlimited without a mint cap.
+#[test]
+fn test_mint_without_cap() {
+    let (mut chain, contract) = initialize_license();
+    for token in 1..=5 {
+        mint(&mut chain, contract, BOB, token).expect("Mint without cap");
+    }
+    assert_eq!(view::<_, u32>(&chain, contract, "mintCountOf", &BOB), 5);
+}
+
+/// Test that the pause events name the `Pauser` who triggered them and the
+/// block time.
+#[test]
+fn test_pause_events() {
+    let (mut chain, contract) = initialize_license();
+    update(&mut chain, contract, ALICE, "grantRole", &RoleParams {
+        address: BOB_ADDR,
+        role:    Role::Pauser,
+    })
+    .expect("Grant pauser");
+    chain.tick_block_time(Duration::from_hours(1)).expect("Block time does not overflow");
+
+    let pause_event = |result: ContractInvokeSuccess| -> CustomEvent {
+        result
+            .events()
+            .flat_map(|(_, events)| events.iter())
+            .find_map(|event| event.parse().ok())
+            .expect("Pause event logged")
+    };
+    let paused = update(&mut chain, contract, BOB, "pause", &()).expect("Bob pauses");
+    assert_eq!(
+        pause_event(paused),
+        CustomEvent::Paused(PauseEvent {
+            pauser:     BOB_ADDR,
+            block_time: chain.block_time(),
+        })
+    );
+
+    chain.tick_block_time(Duration::from_hours(1)).expect("Block time does not overflow");
+    let unpaused = update(&mut chain, contract, BOB, "unpause", &()).expect("Bob unpauses");
+    assert_eq!(
+        pause_event(unpaused),
+        CustomEvent::Unpaused(PauseEvent {
+            pauser:     BOB_ADDR,
+            block_time: chain.block_time(),
+        })
+    );
+}
+
+/// Test that `getOwner` returns the deployer initially and the new owner after
+/// `transferOwnership`.
+#[test]
+fn test_get_owner() {
+    let (mut chain, contract) = initialize_license();
+    assert_eq!(view::<_, Address>(&chain, contract, "getOwner", &()), ALICE_ADDR);
+
+    update(&mut chain, contract, ALICE, "transferOwnership", &TransferOwnershipParams {
+        new_owner:            BOB_ADDR,
+        allow_contract_owner: false,
+    })
+    .expect("Owner transfers ownership");
+    assert_eq!(view::<_, Address>(&chain, contract, "getOwner", &()), BOB_ADDR);
+}
+
+/// Test that a transfer batch exceeding the log limit is rejected as a whole
+/// before any token moves.
+#[test]
+fn test_transfer_log_limit() {
+    let (mut chain, contract) = initialize_license();
+    for token in 1..=33 {
+        mint(&mut chain, contract, BOB, token).expect("Mint token");
+    }
+    let transfers = |count: u32| {
+        TransferParams::<TokenIdU32, TokenAmountU64>(
+            (1..=count)
+                .map(|token| Transfer {
+                    token_id: TokenIdU32(token),
+                    amount:   TokenAmountU64(1),
+                    from:     BOB_ADDR,
+                    to:       Receiver::Account(CHARLIE),
+                    data:     AdditionalData::empty(),
+                })
+                .collect(),
+        )
+    };
+
+    let rejected = update(&mut chain, contract, BOB, "transfer", &transfers(33))
+        .expect_err("Too many events");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::LogFull));
+    for token in 1..=33 {
+        assert_eq!(balance_of(&chain, contract, BOB_ADDR, token), TokenAmountU64(1));
+    }
+
+    update(&mut chain, contract, BOB, "transfer", &transfers(32)).expect("Within the limit");
+    assert_eq!(balance_of(&chain, contract, CHARLIE_ADDR, 32), TokenAmountU64(1));
+}
+
+/// Test that renouncing the ownership requires the confirmation and leaves the
+/// owner-gated entrypoints unusable.
+#[test]
+fn test_renounce_ownership() {
+    let (mut chain, contract) = initialize_license();
+    let renounce = |confirmation: &str| RenounceOwnershipParams {
+        confirmation: confirmation.to_string(),
+    };
+
+    let rejected = update(&mut chain, contract, ALICE, "renounceOwnership", &renounce("renounce"))
+        .expect_err("Wrong confirmation");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::RenounceNotConfirmed));
+    update(&mut chain, contract, BOB, "renounceOwnership", &renounce(RENOUNCE_CONFIRMATION))
+        .expect_err("Only the owner");
+
+    update(&mut chain, contract, ALICE, "renounceOwnership", &renounce(RENOUNCE_CONFIRMATION))
+        .expect("Owner renounces");
+    assert_eq!(view::<_, Address>(&chain, contract, "getOwner", &()), RENOUNCED_OWNER);
+
+    let rejected = mint(&mut chain, contract, BOB, 1).expect_err("No one can mint");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Unauthorized);
+    update(&mut chain, contract, ALICE, "setContractInfo", &ContractInfo::default())
+        .expect_err("Former owner is not the owner");
+    update(&mut chain, contract, ALICE, "grantRole", &RoleParams {
+        address: ALICE_ADDR,
+        role:    Role::Minter,
+    })
+    .expect_err("Former owner is no admin");
+}
+
+/// Test that `balanceOfLenient` returns 0 for token IDs which do not exist,
+/// while `balanceOf` rejects the whole query.
+#[test]
+fn test_balance_of_lenient() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    mint(&mut chain, contract, CHARLIE, 3).expect("Mint token 3");
+
+    let params = BalanceOfQueryParams {
+        queries: [(1, BOB_ADDR), (2, BOB_ADDR), (3, BOB_ADDR), (3, CHARLIE_ADDR), (4, CHARLIE_ADDR)]
+            .into_iter()
+            .map(|(token, address)| BalanceOfQuery {
+                token_id: TokenIdU32(token),
+                address,
+            })
+            .collect(),
+    };
+    let response: BalanceOfQueryResponse<TokenAmountU64> =
+        view(&chain, contract, "balanceOfLenient", &params);
+    assert_eq!(response.0, [1, 0, 0, 1, 0].map(TokenAmountU64));
+
+    let rejected = chain
+        .contract_invoke(ALICE, ALICE_ADDR, Energy::from(100_000), UpdateContractPayload {
+            address:      contract,
+            amount:       Amount::zero(),
+            receive_name: receive_name("balanceOf"),
+            message:      OwnedParameter::from_serial(&params)
+                .expect("Parameter within size bounds"),
+        })
+        .expect_err("Strict query rejects unknown tokens");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::InvalidTokenId);
+}
+
+/// Test that the issuance time of a license is the block time at mint and is
+/// kept when the license is renewed.
+#[test]
+fn test_issuance_info() {
+    let (mut chain, contract) = initialize_license();
+    chain.tick_block_time(Duration::from_days(1)).expect("Block time does not overflow");
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+    let minted_at = chain.block_time();
+
+    chain.tick_block_time(Duration::from_days(1)).expect("Block time does not overflow");
+    mint(&mut chain, contract, BOB, 2).expect("Mint token 2");
+    update(&mut chain, contract, ALICE, "renew", &RenewParams {
+        token_id:   TokenIdU32(1),
+        new_expiry: Timestamp::from_timestamp_millis(u64::MAX),
+    })
+    .expect("Renew token 1");
+
+    let issued: Vec<Option<Timestamp>> =
+        view(&chain, contract, "issuanceInfo", &vec![TokenIdU32(1), TokenIdU32(2), TokenIdU32(3)]);
+    assert_eq!(issued, [Some(minted_at), Some(chain.block_time()), None]);
+    assert_ne!(minted_at, chain.block_time());
+}
+
+
+/// Test that `mintBatch` rejects a frozen recipient without minting any token,
+/// while minting to an account which is not frozen succeeds.
+#[test]
+fn test_mint_batch_frozen_recipient() {
+    let (mut chain, contract) = initialize_license();
+    update(&mut chain, contract, ALICE, "setFrozen", &SetFrozenParams {
+        address: CHARLIE_ADDR,
+        frozen:  true,
+    })
+    .expect("Freeze Charlie");
+    let batch = |owner: AccountAddress| MintBatchParams {
+        owner,
+        tokens: (1..=2).map(|token| (TokenIdU32(token), "@license".to_string())).collect(),
+    };
+
+    let rejected =
+        update(&mut chain, contract, ALICE, "mintBatch", &batch(CHARLIE)).expect_err("Frozen");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::AddressFrozen));
+
+    update(&mut chain, contract, ALICE, "mintBatch", &batch(BOB)).expect("Bob is not frozen");
+    assert_eq!(balance_of(&chain, contract, BOB_ADDR, 2), TokenAmountU64(1));
+}
+
+/// Test that duplicate standards in a `supports` query get identical results
+/// in the order of the queries.
+#[test]
+fn test_supports_duplicates() {
+    let (mut chain, contract) = initialize_license();
+    let implementors = vec![ContractAddress::new(42, 0), ContractAddress::new(43, 0)];
+    update(&mut chain, contract, ALICE, "setImplementors", &SetImplementorsParams {
+        id:           StandardIdentifierOwned::new_unchecked("CIS-5".to_string()),
+        implementors: implementors.clone(),
+    })
+    .expect("Owner sets implementors");
+
+    let params = SupportsQueryParams {
+        queries: ["CIS-5", "CIS-2", "CIS-5", "CIS-9", "CIS-9", "CIS-5"]
+            .iter()
+            .map(|id| StandardIdentifierOwned::new_unchecked(id.to_string()))
+            .collect(),
+    };
+    let response: SupportsQueryResponse = view(&chain, contract, "supports", &params);
+    assert_eq!(response.results.len(), 6);
+    for (index, result) in response.results.iter().enumerate() {
+        match index {
+            0 | 2 | 5 => {
+                assert!(matches!(result, SupportResult::SupportBy(a) if *a == implementors))
+            }
+            1 => assert!(matches!(result, SupportResult::Support)),
+            _ => assert!(matches!(result, SupportResult::NoSupport)),
+        }
+    }
+}
+
+/// Test that the owner can withdraw CCD held by the contract, but not more
+/// than it holds.
+#[test]
+fn test_withdraw() {
+    let (mut chain, _) = initialize_license();
+    let contract = chain
+        .contract_init(SIGNER, ALICE, Energy::from(10_000), InitContractPayload {
+            amount:    Amount::from_ccd(100),
+            mod_ref:   module_reference(),
+            init_name: OwnedContractName::new_unchecked("init_LicenseContract".to_string()),
+            param:     OwnedParameter::from_serial(&InitParams::default())
+                .expect("Parameter is valid."),
+        })
+        .expect("Initializing funded contract")
+        .contract_address;
+    let balance = |chain: &Chain| chain.contract_balance(contract).expect("Contract exists");
+    assert_eq!(balance(&chain), Amount::from_ccd(100));
+
+    let withdraw = |amount: Amount| WithdrawParams {
+        to: CHARLIE,
+        amount,
+    };
+    update(&mut chain, contract, BOB, "withdraw", &withdraw(Amount::from_ccd(40)))
+        .expect_err("Only the owner");
+    let rejected = update(&mut chain, contract, ALICE, "withdraw", &withdraw(Amount::from_ccd(101)))
+        .expect_err("More than the balance");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::InsufficientBalance));
+
+    let charlie_before = chain.account_balance_available(CHARLIE).expect("Charlie exists");
+    update(&mut chain, contract, ALICE, "withdraw", &withdraw(Amount::from_ccd(40)))
+        .expect("Owner withdraws");
+    assert_eq!(balance(&chain), Amount::from_ccd(60));
+    assert_eq!(
+        chain.account_balance_available(CHARLIE),
+        Some(charlie_before + Amount::from_ccd(40))
+    );
+}
+
+/// Test that an owner and global operators given at initialization take over
+/// the privileges of the deployer and can mint right away.
+#[test]
+fn test_init_owner_and_operators() {
+    let (mut chain, contract) = initialize_license_with(InitParams {
+        owner: Some(BOB),
+        initial_operators: vec![CHARLIE_ADDR, DAVE_ADDR],
+        ..Default::default()
+    });
+    assert_eq!(view::<_, Address>(&chain, contract, "getOwner", &()), BOB_ADDR);
+    let operators: Vec<Address> = view(&chain, contract, "globalOperators", &());
+    assert_eq!(operators, [CHARLIE_ADDR, DAVE_ADDR]);
+
+    mint_as(&mut chain, contract, BOB, BOB, 1).expect("Owner mints");
+    mint_as(&mut chain, contract, CHARLIE, BOB, 2).expect("Operator mints");
+    let rejected = mint_as(&mut chain, contract, ALICE, BOB, 3).expect_err("Deployer is no minter");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Unauthorized);
+}
+
+/// Test that `metadataUrlOf` returns the same as `tokenMetadata` for a single
+/// token and rejects unknown tokens.
+#[test]
+fn test_metadata_url_of() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token 1");
+
+    let single: MetadataUrl = view(&chain, contract, "metadataUrlOf", &TokenIdU32(1));
+    assert_eq!(single, token_metadata(&chain, contract, 1));
+
+    let rejected = chain
+        .contract_invoke(ALICE, ALICE_ADDR, Energy::from(100_000), UpdateContractPayload {
+            address:      contract,
+            amount:       Amount::zero(),
+            receive_name: receive_name("metadataUrlOf"),
+            message:      OwnedParameter::from_serial(&TokenIdU32(2))
+                .expect("Parameter within size bounds"),
+        })
+        .expect_err("Unknown token rejects");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::InvalidTokenId);
+}
+
+/// Test that after a sequence of random mints, transfers and burns the count
+/// from `balanceCountOf` matches the owned tokens of each address in `view`.
+#[test]
+fn test_balance_count_of() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let (mut chain, contract) = initialize_license();
+    let holders = [(BOB, BOB_ADDR), (CHARLIE, CHARLIE_ADDR), (ALICE, ALICE_ADDR)];
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut next_token = 1;
+    for _ in 0..60 {
+        let (owner, owner_addr) = holders[rng.gen_range(0..holders.len())];
+        let state: ViewState = view(&chain, contract, "view", &());
+        let owned: Vec<TokenIdU32> = state
+            .state
+            .iter()
+            .find(|(address, _)| *address == owner_addr)
+            .map(|(_, address_state)| address_state.owned_tokens.clone())
+            .unwrap_or_default();
+        match (rng.gen_range(0..3), owned.first()) {
+            (1, Some(token)) => {
+                let (_, to) = holders[rng.gen_range(0..holders.len())];
+                transfer(&mut chain, contract, owner, owner_addr, to, token.0).expect("Transfer");
+            }
+            (2, Some(token)) => {
+                burn(&mut chain, contract, owner, owner_addr, token.0).expect("Burn");
+            }
+            _ => {
+                mint(&mut chain, contract, owner, next_token).expect("Mint");
+                next_token += 1;
+            }
+        }
+    }
+
+    let addresses: Vec<Address> = holders.iter().map(|(_, address)| *address).collect();
+    let counts: Vec<u32> = view(&chain, contract, "balanceCountOf", &addresses);
+    let state: ViewState = view(&chain, contract, "view", &());
+    for (address, count) in addresses.iter().zip(counts) {
+        let owned = state
+            .state
+            .iter()
+            .find(|(holder, _)| holder == address)
+            .map_or(0, |(_, address_state)| address_state.owned_tokens.len());
+        assert_eq!(count as usize, owned, "Count of {:?}", address);
+    }
+}
+
+/// Test that `transferAndCall` keeps the transfer when the callback succeeds,
+/// and undoes it when the callback rejects.
+#[test]
+fn test_transfer_and_call() {
+    let (mut chain, contract) = initialize_license();
+    mint(&mut chain, contract, BOB, 1).expect("Mint token");
+    let owner_of = |chain: &Chain| -> Option<Address> {
+        let owners: Vec<Option<Address>> =
+            view(chain, contract, "tokenOwner", &TokenOwnerQueryParams {
+                queries: vec![TokenIdU32(1)],
+            });
+        owners[0]
+    };
+    let params = |to: AccountAddress, entrypoint: &str, parameter: OwnedParameter| {
+        TransferAndCallParams {
+            transfer: Transfer {
+                token_id: TokenIdU32(1),
+                amount:   TokenAmountU64(1),
+                from:     BOB_ADDR,
+                to:       Receiver::Account(to),
+                data:     AdditionalData::empty(),
+            },
+            callback: TransferCallback {
+                address: contract,
+                entrypoint: OwnedEntrypointName::new_unchecked(entrypoint.to_string()),
+                parameter,
+            },
+        }
+    };
+
+    // Calling `burn` without a parameter rejects, which undoes the transfer.
+    let rejected = update(
+        &mut chain,
+        contract,
+        BOB,
+        "transferAndCall",
+        &params(CHARLIE, "burn", OwnedParameter::empty()),
+    )
+    .expect_err("Callback rejects");
+    let error: ContractError = rejected.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::CallbackFailed));
+    assert_eq!(owner_of(&chain), Some(BOB_ADDR));
+
+    update(
+        &mut chain,
+        contract,
+        BOB,
+        "transferAndCall",
+        &params(CHARLIE, "view", OwnedParameter::empty()),
+    )
+    .expect("Callback succeeds");
+    assert_eq!(owner_of(&chain), Some(CHARLIE_ADDR));
+}
+
+/// Helper method for minting a token of a tier to `owner`.
+fn mint_tier(
+    chain: &mut Chain,
+    contract: ContractAddress,
+    owner: AccountAddress,
+    token: u32,
+    tier: u8,
+) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+    update(chain, contract, ALICE, "mint", &MintParams {
+        owner,
+        token: TokenIdU32(token),
+        web3id: "@license".to_string(),
+        hash: None,
+        content_type: None,
+        expiry: None,
+        tier,
+    })
+}
+
+/// Test that the tier of a license is stored on mint and that `tokensByTier`
+/// lists the tokens of one tier only.
+#[test]
+fn test_tiers() {
+    let (mut chain, contract) = initialize_license();
+    for token in 1..=6 {
+        mint_tier(&mut chain, contract, BOB, token, (token % 3) as u8 + 1).expect("Mint token");
+    }
+    mint(&mut chain, contract, BOB, 7).expect("Mint without a tier");
+
+    let tiers: Vec<Option<u8>> =
+        view(&chain, contract, "tierOf", &vec![TokenIdU32(1), TokenIdU32(3), TokenIdU32(8)]);
+    assert_eq!(tiers, vec![Some(2), Some(1), None]);
+
+    let by_tier = |chain: &Chain, tier: u8, skip: u32, take: u32| -> Vec<TokenIdU32> {
+        view(chain, contract, "tokensByTier", &TokensByTierParams {
+            tier,
+            skip,
+            take,
+        })
+    };
+    assert_eq!(by_tier(&chain, 2, 0, 10), vec![TokenIdU32(1), TokenIdU32(4)]);
+    assert_eq!(by_tier(&chain, 3, 1, 1), vec![TokenIdU32(5)]);
+    assert_eq!(by_tier(&chain, 0, 0, 10), vec![TokenIdU32(7)]);
+    assert!(by_tier(&chain, 4, 0, 10).is_empty());
+
+    // Burned tokens are removed from their tier.
+    burn(&mut chain, contract, BOB, BOB_ADDR, 4).expect("Burn token 4");
+    assert_eq!(by_tier(&chain, 2, 0, 10), vec![TokenIdU32(1)]);
+}
+
+/// Helper method for building an `updateOperatorSigned` parameter signed by
+/// `signer`.
+fn sign_operator_update(
+    chain: &Chain,
+    contract: ContractAddress,
+    keys: &AccountKeys,
+    signer: AccountAddress,
+    message: UpdateOperatorSignedMessage,
+) -> UpdateOperatorSignedParams {
+    let mut params = UpdateOperatorSignedParams {
+        signature: AccountSignatures {
+            sigs: Default::default(),
+        },
+        signer,
+        message,
+    };
+    let message_hash: [u8; 32] = view(chain, contract, "viewOperatorMessageHash", &params);
+    params.signature = keys.sign_message(&message_hash);
+    params
+}
+
+/// Test that a sponsor can submit an operator update signed by the owner, and
+/// that the signed update can neither be replayed nor used after it expired.
+#[test]
+fn test_update_operator_signed() {
+    let (mut chain, contract) = initialize_license();
+    let keys = create_signing_account(&mut chain, DAVE);
+
+    let expiry = Timestamp::from_timestamp_millis(0).checked_add(Duration::from_days(1)).unwrap();
+    let message = |nonce: u64, operator: Address| UpdateOperatorSignedMessage {
+        contract_address: contract,
+        nonce,
+        timestamp: expiry,
+        updates: UpdateOperatorParams(vec![UpdateOperator {
+            update: OperatorUpdate::Add,
+            operator,
+        }]),
+    };
+    let params = sign_operator_update(&chain, contract, &keys, DAVE, message(0, BOB_ADDR));
+
+    // Charlie sponsors the operator update signed by Dave.
+    let executed = update(&mut chain, contract, CHARLIE, "updateOperatorSigned", &params)
+        .expect("Signed update");
+    let operator_of: OperatorOfQueryResponse =
+        view(&chain, contract, "operatorOf", &OperatorOfQueryParams {
+            queries: vec![OperatorOfQuery {
+                owner:   DAVE_ADDR,
+                address: BOB_ADDR,
+            }],
+        });
+    assert_eq!(operator_of.0, [true]);
+    let events: Vec<CustomEvent> = executed
+        .events()
+        .flat_map(|(_, events)| events.iter().filter_map(|e| e.parse().ok()))
+        .collect();
+    assert_eq!(events, [CustomEvent::SponsoredExecuted(SponsoredExecutedEvent {
+        signer: DAVE,
+        nonce:  0,
+        action: SponsoredAction::UpdateOperator,
+    })]);
+
+    // Replaying the message is rejected.
+    let replayed = update(&mut chain, contract, CHARLIE, "updateOperatorSigned", &params)
+        .expect_err("Replay");
+    let error: ContractError = replayed.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::NonceMismatch));
+
+    // Expired messages are rejected.
+    let params = sign_operator_update(&chain, contract, &keys, DAVE, message(1, CHARLIE_ADDR));
+    chain.tick_block_time(Duration::from_days(2)).expect("Block time does not overflow");
+    let expired = update(&mut chain, contract, CHARLIE, "updateOperatorSigned", &params)
+        .expect_err("Expired");
+    let error: ContractError = expired.parse_return_value().expect("Deserialize error");
+    assert_eq!(error, ContractError::Custom(CustomContractError::Expired));
 }